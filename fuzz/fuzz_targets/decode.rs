@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use library::{client_message_to_json, describe_capture, parse_capture, server_message_to_json};
+
+// every decoder has to turn arbitrary bytes into an error instead of panicking
+fuzz_target!(|data: &[u8]| {
+    _ = client_message_to_json(data);
+    _ = server_message_to_json(data);
+
+    if let Ok(records) = parse_capture(data) {
+        _ = describe_capture(&records);
+    }
+});
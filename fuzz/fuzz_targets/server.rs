@@ -0,0 +1,83 @@
+#![no_main]
+
+use futures::{FutureExt, SinkExt, StreamExt};
+use libfuzzer_sys::fuzz_target;
+use library::{run_with_acceptor_async, MemoryAcceptor, MemoryConnector, RateLimits, ServerConfig};
+use tokio::io::DuplexStream;
+use tokio_util::bytes::Bytes;
+use tokio_util::codec::{Framed, LengthDelimitedCodec};
+
+const PASSWORD: &str = "password";
+const MAX_PACKET_LENGTH: usize = 1024;
+
+// flags of the messages used to drive the connection, see `ClientMessage` and `ServerMessage`
+const AUTHORIZATION: u8 = 0;
+const LIST_OF_ROOMS: u8 = 11;
+const LIST_OF_ROOMS_RESPONSE: u8 = 16;
+
+type Stream = Framed<DuplexStream, LengthDelimitedCodec>;
+
+// two authorized players send arbitrary frames, `true` picks the second one; the server runs in
+// this task so a panic while processing a message crashes the target
+fuzz_target!(|frames: Vec<(bool, Vec<u8>)>| {
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    runtime.block_on(async move {
+        let (acceptor, connector) = MemoryAcceptor::new();
+
+        // throttling would cut long inputs short
+        let mut config = ServerConfig::new(PASSWORD);
+        config.limits = RateLimits { messages: None, max_violations: None, ..RateLimits::default() };
+        tokio::select! {
+            _ = run_with_acceptor_async(acceptor, config) => {}
+            _ = play(connector, frames) => {}
+        }
+    });
+});
+
+async fn play(connector: MemoryConnector, frames: Vec<(bool, Vec<u8>)>) {
+    let (Some(mut first), Some(mut second)) = (connect(&connector, "alice").await, connect(&connector, "bob").await) else {
+        return;
+    };
+
+    for (use_second, mut frame) in frames {
+        frame.truncate(MAX_PACKET_LENGTH);
+        let (stream, other) = if use_second { (&mut second, &mut first) } else { (&mut first, &mut second) };
+
+        // the other player's pushes have to be read, or its connection stops reading requests
+        while let Some(Some(_)) = other.next().now_or_never() {}
+
+        // the server processes messages in order, the rooms response marks the frame as handled
+        if stream.send(Bytes::from(frame)).await.is_err()
+            || stream.send(Bytes::from(vec![LIST_OF_ROOMS])).await.is_err() {
+            return;
+        }
+        loop {
+            match stream.next().await {
+                Some(Ok(frame)) if frame.first() == Some(&LIST_OF_ROOMS_RESPONSE) => break,
+                Some(Ok(_)) => {}
+                _ => return,
+            }
+        }
+    }
+}
+
+async fn connect(connector: &MemoryConnector, name: &str) -> Option<Stream> {
+    let mut stream = LengthDelimitedCodec::builder()
+        .length_field_type::<u16>()
+        .little_endian()
+        .max_frame_length(MAX_PACKET_LENGTH)
+        .new_framed(connector.connect().ok()?);
+
+    // request authorization, then the id
+    stream.next().await?.ok()?;
+
+    let mut frame = vec![AUTHORIZATION];
+    for text in [PASSWORD, name, name] {
+        frame.extend_from_slice(&(text.len() as u16).to_le_bytes());
+        frame.extend_from_slice(text.as_bytes());
+    }
+    stream.send(Bytes::from(frame)).await.ok()?;
+    stream.next().await?.ok()?;
+
+    Some(stream)
+}
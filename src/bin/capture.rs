@@ -0,0 +1,45 @@
+use std::path::Path;
+use library::{describe_capture, read_capture, replay_capture_async, ReplayPasswords};
+
+const USAGE: &str = "usage: capture decode <file>\n       \
+    capture replay <file> <addr> <password> [<admin password> [<account password> [<room password>]]]";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["decode", path] => {
+            for line in describe_capture(&read_capture(Path::new(path))?) {
+                println!("{}", line);
+            }
+        }
+        ["replay", path, addr, password, rest @ ..] if rest.len() <= 3 => {
+            // passwords that aren't given are sent blank
+            let optional = |i: usize| rest.get(i).map(|p| p.to_string()).unwrap_or_default();
+            let passwords = ReplayPasswords {
+                server: password.to_string(),
+                admin: optional(0),
+                account: optional(1),
+                room: optional(2),
+            };
+
+            let records = read_capture(Path::new(path))?;
+            let report = replay_capture_async(&records, addr.parse()?, &passwords).await?;
+
+            println!("sent {} frames, compared {} frames", report.frames_sent, report.frames_compared);
+            for mismatch in &report.mismatches {
+                println!("record {}: expected {:?}, got {:?}", mismatch.record, mismatch.expected, mismatch.actual);
+            }
+            if !report.mismatches.is_empty() {
+                anyhow::bail!("{} frames differ from the capture", report.mismatches.len());
+            }
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    }
+
+    Ok(())
+}
@@ -1,8 +1,6 @@
-use std::io::Error;
 use std::ops::Deref;
 use anyhow::bail;
 use tokio_util::bytes::{Bytes, BytesMut};
-use crate::server::TcpMessage;
 
 pub struct BinReader<'a> {
     buffer: &'a [u8],
@@ -28,30 +26,56 @@ impl<'a> BinReader<'a> {
         }
     }
 
-    pub fn read_u8(&mut self) -> u8 {
-        let value = self.buffer[self.position];
-        self.position += 1;
+    pub fn from_slice(buffer: &'a [u8]) -> Self {
+        Self {
+            buffer,
+            position: 0
+        }
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.buffer.len() - self.position
+    }
+
+    pub fn skip(&mut self, len: usize) -> anyhow::Result<()> {
+        self.take(len)?;
+        Ok(())
+    }
+
+    pub fn read_u8(&mut self) -> anyhow::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
 
-        value
+    pub fn read_u16(&mut self) -> anyhow::Result<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
     }
 
-    pub fn read_u16(&mut self) -> u16 {
-        let bytes = &self.buffer[self.position..self.position + 2];
-        self.position += 2;
-        u16::from_le_bytes([bytes[0], bytes[1]])
+    pub fn read_u32(&mut self) -> anyhow::Result<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
     }
 
-    pub fn read_u32(&mut self) -> u32 {
-        let bytes = &self.buffer[self.position..self.position + 4];
-        self.position += 4;
-        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    pub fn read_u64(&mut self) -> anyhow::Result<u64> {
+        let bytes = self.take(8)?;
+        Ok(u64::from_le_bytes(bytes.try_into()?))
     }
 
-    pub fn read_str(&mut self) -> String {
-        let len = self.read_u16() as usize; // Read string length
-        let bytes = &self.buffer[self.position..self.position + len];
-        self.position += len;
+    pub fn read_str(&mut self) -> anyhow::Result<String> {
+        let len = self.read_u16()? as usize; // Read string length
+        let bytes = self.take(len)?;
 
-        String::from_utf8(bytes.to_vec()).expect("Invalid UTF-8")
+        Ok(String::from_utf8(bytes.to_vec())?)
     }
-}
\ No newline at end of file
+
+    /// Returns the next `len` bytes, frames from the network can end anywhere so nothing here panics.
+    fn take(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+        let end = self.position.saturating_add(len);
+        let Some(bytes) = self.buffer.get(self.position..end) else {
+            bail!("Unexpected end of data, {} bytes missing", end - self.buffer.len());
+        };
+
+        self.position = end;
+        Ok(bytes)
+    }
+}
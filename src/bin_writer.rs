@@ -1,4 +1,3 @@
-
 pub struct BinWriter {
     buffer: Vec<u8>,
     position: usize
@@ -13,6 +12,7 @@ impl BinWriter {
     }
 
     pub fn write_u8(&mut self, value: u8) {
+        self.reserve(1);
         self.buffer[self.position] = value;
         self.position += 1;
     }
@@ -25,23 +25,33 @@ impl BinWriter {
         self.write_u16(len);
 
         // Write the string bytes
+        self.reserve(bytes.len());
         self.buffer[self.position..self.position + bytes.len()]
             .copy_from_slice(bytes);
         self.position += bytes.len();
     }
 
     pub fn write_u16(&mut self, value: u16) {
+        self.reserve(2);
         self.buffer[self.position..self.position + 2]
             .copy_from_slice(&value.to_le_bytes());
         self.position += 2;
     }
 
     pub fn write_u32(&mut self, value: u32) {
+        self.reserve(4);
         self.buffer[self.position..self.position + 4]
             .copy_from_slice(&value.to_le_bytes());
         self.position += 4;
     }
 
+    pub fn write_u64(&mut self, value: u64) {
+        self.reserve(8);
+        self.buffer[self.position..self.position + 8]
+            .copy_from_slice(&value.to_le_bytes());
+        self.position += 8;
+    }
+
     pub fn clone_data(&self) -> Vec<u8> {
         self.buffer[..self.position].to_vec()
     }
@@ -53,4 +63,11 @@ impl BinWriter {
     pub fn clear(&mut self) {
         self.position = 0;
     }
-}
\ No newline at end of file
+
+    // grow the buffer when a write doesn't fit into the initial capacity
+    fn reserve(&mut self, additional: usize) {
+        if self.position + additional > self.buffer.len() {
+            self.buffer.resize(self.position + additional, 0);
+        }
+    }
+}
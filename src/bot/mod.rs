@@ -0,0 +1,304 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+use crate::client::{Client, StreamedMessage};
+use crate::server::{reveal_hint, AttemptFeedback, GameError, MatchMode, MatchOutcome, MatchScore};
+use crate::utils::ClientId;
+
+mod strategy;
+pub use strategy::{FilteringStrategy, GuessStrategy, SequentialStrategy};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BotRole {
+    /// Picks words, challenges other players and sends hints.
+    Challenger,
+    /// Answers challenges and makes attempts.
+    Guesser,
+}
+
+/// Match a challenger bot wants to start.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Challenge {
+    pub opponent_id: ClientId,
+    pub word: String,
+    pub mode: MatchMode,
+}
+
+/// Automated player driven by `run_bot_async`. The bot only makes decisions, the runner talks to the server.
+pub trait Bot: Send {
+    fn role(&self) -> BotRole;
+
+    /// Challenger bots pick an opponent and a word while they are not in a match.
+    fn challenge(&mut self, _opponents: &[ClientId]) -> Option<Challenge> {
+        None
+    }
+
+    /// Hint challenger bots send after a wrong attempt.
+    fn hint(&mut self, _attempt: &str, _feedback: &AttemptFeedback) -> Option<String> {
+        None
+    }
+
+    /// Called when a guesser bot was challenged or started a solo match.
+    fn on_match_began(&mut self) {}
+
+    /// Next attempt of a guesser bot, `None` waits for hints.
+    fn next_attempt(&mut self) -> Option<String> {
+        None
+    }
+
+    fn on_attempt_result(&mut self, _attempt: &str, _feedback: &AttemptFeedback) {}
+
+    fn on_hint(&mut self, _hint: &str) {}
+
+    fn on_match_ended(&mut self, _score: &MatchScore) {}
+}
+
+pub struct BotConfig {
+    pub addr: SocketAddr,
+    /// Bots on the server's host connect over this Unix domain socket instead of `addr` when set.
+    pub unix_path: Option<PathBuf>,
+    pub password: String,
+    /// Account name, empty bots are unrated.
+    pub name: String,
+    pub account_password: String,
+    /// The runner returns after this many finished matches, runs until disconnected when `None`.
+    pub max_matches: Option<usize>,
+    /// Guesser bots start solo matches in this mode while nobody challenges them.
+    pub solo: Option<MatchMode>,
+    /// How long an idle challenger waits before looking for opponents again.
+    pub poll_interval: Duration,
+}
+
+impl BotConfig {
+    pub fn new(addr: SocketAddr, password: &str) -> BotConfig {
+        BotConfig {
+            addr,
+            unix_path: None,
+            password: password.to_string(),
+            name: String::new(),
+            account_password: String::new(),
+            max_matches: None,
+            solo: None,
+            poll_interval: Duration::from_millis(100),
+        }
+    }
+}
+
+/// Connects the bot and drives it from the server's streamed messages. Returns the bot once
+/// `max_matches` matches finished.
+pub async fn run_bot_async<B: Bot>(config: BotConfig, mut bot: B) -> anyhow::Result<B> {
+    let mut client = connect_async(&config).await?;
+    let mut finished = 0;
+    let mut in_match = false;
+
+    while config.max_matches.is_none_or(|max| finished < max) {
+        match client.read_streamed_message_async().await? {
+            Some(StreamedMessage::Challenged) => {
+                in_match = true;
+                bot.on_match_began();
+                make_attempts_async(&mut client, &mut bot).await?;
+            }
+            Some(StreamedMessage::Hint(hint)) => {
+                bot.on_hint(&hint);
+                make_attempts_async(&mut client, &mut bot).await?;
+            }
+            Some(StreamedMessage::Attempt(correct, word)) => {
+                let feedback = AttemptFeedback { correct, letters: Vec::new() };
+                send_hint_async(&mut client, &mut bot, &word, &feedback).await?;
+            }
+            Some(StreamedMessage::AttemptWithFeedback(feedback, word)) => {
+                send_hint_async(&mut client, &mut bot, &word, &feedback).await?;
+            }
+            Some(StreamedMessage::MatchEnded(score)) => {
+                in_match = false;
+                finished += 1;
+                bot.on_match_ended(&score);
+            }
+            Some(_) => {}
+            None if !in_match => {
+                in_match = start_match_async(&mut client, &mut bot, &config).await?;
+                if !in_match {
+                    tokio::time::sleep(config.poll_interval).await;
+                }
+            }
+            None => {}
+        }
+    }
+
+    Ok(bot)
+}
+
+async fn connect_async(config: &BotConfig) -> anyhow::Result<Client> {
+    #[cfg(unix)]
+    if let Some(path) = &config.unix_path {
+        return Client::connect_unix_async(path, &config.password, &config.name, &config.account_password).await;
+    }
+
+    Client::connect_as_async(config.addr, &config.password, &config.name, &config.account_password).await
+}
+
+/// Challenges an opponent or starts a solo match, returns whether a match began.
+async fn start_match_async<B: Bot>(client: &mut Client, bot: &mut B, config: &BotConfig) -> anyhow::Result<bool> {
+    match bot.role() {
+        BotRole::Challenger => {
+            let opponents = client.get_opponents_async().await?;
+            let Some(challenge) = bot.challenge(&opponents) else {
+                return Ok(false);
+            };
+
+            // the opponent could have been challenged by someone else in the meantime
+            let result = client
+                .request_match_with_mode_async(challenge.opponent_id, &challenge.word, challenge.mode)
+                .await;
+            ignore_game_error(result.map(|_| true), false)
+        }
+        BotRole::Guesser => {
+            let Some(mode) = config.solo else {
+                return Ok(false);
+            };
+
+            client.request_solo_match_async(mode, None).await?;
+            bot.on_match_began();
+            make_attempts_async(client, bot).await?;
+            Ok(true)
+        }
+    }
+}
+
+/// Makes attempts until the word is guessed or the bot runs out of attempts.
+async fn make_attempts_async<B: Bot>(client: &mut Client, bot: &mut B) -> anyhow::Result<()> {
+    while let Some(attempt) = bot.next_attempt() {
+        // rejected attempts, e.g. of the wrong length in wordle matches, are skipped
+        let result = client.send_attempt_with_feedback_async(&attempt).await.map(Some);
+        let Some(feedback) = ignore_game_error(result, None)? else {
+            continue;
+        };
+
+        bot.on_attempt_result(&attempt, &feedback);
+        if feedback.correct {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+async fn send_hint_async<B: Bot>(
+    client: &mut Client,
+    bot: &mut B,
+    attempt: &str,
+    feedback: &AttemptFeedback) -> anyhow::Result<()> {
+    if feedback.correct {
+        return Ok(());
+    }
+
+    if let Some(hint) = bot.hint(attempt, feedback) {
+        // hints over the server's limits are dropped
+        ignore_game_error(client.send_hint_async(&hint).await, ())?;
+    }
+
+    Ok(())
+}
+
+/// Turns errors the server reported for a request into `fallback`, connection errors are kept.
+fn ignore_game_error<T>(result: anyhow::Result<T>, fallback: T) -> anyhow::Result<T> {
+    match result {
+        Err(err) if err.downcast_ref::<GameError>().is_some() => Ok(fallback),
+        result => result,
+    }
+}
+
+/// Challenges the first available opponent with words from a list and reveals a letter with every hint.
+pub struct ChallengerBot {
+    words: Vec<String>,
+    mode: MatchMode,
+    next_word: usize,
+    current: String,
+    revealed: usize,
+}
+
+impl ChallengerBot {
+    pub fn new(words: Vec<String>, mode: MatchMode) -> ChallengerBot {
+        ChallengerBot {
+            words,
+            mode,
+            next_word: 0,
+            current: String::new(),
+            revealed: 0,
+        }
+    }
+}
+
+impl Bot for ChallengerBot {
+    fn role(&self) -> BotRole {
+        BotRole::Challenger
+    }
+
+    fn challenge(&mut self, opponents: &[ClientId]) -> Option<Challenge> {
+        let opponent_id = *opponents.first()?;
+        if self.words.is_empty() {
+            return None;
+        }
+
+        self.current = self.words[self.next_word % self.words.len()].clone();
+        self.next_word += 1;
+        self.revealed = 0;
+
+        Some(Challenge {
+            opponent_id,
+            word: self.current.clone(),
+            mode: self.mode,
+        })
+    }
+
+    fn hint(&mut self, _attempt: &str, _feedback: &AttemptFeedback) -> Option<String> {
+        self.revealed += 1;
+        reveal_hint(&self.current, self.revealed)
+    }
+}
+
+/// Answers challenges with attempts picked by a `GuessStrategy`.
+pub struct GuesserBot<S: GuessStrategy> {
+    pub strategy: S,
+    pub solved: usize,
+    pub failed: usize,
+}
+
+impl<S: GuessStrategy> GuesserBot<S> {
+    pub fn new(strategy: S) -> GuesserBot<S> {
+        GuesserBot {
+            strategy,
+            solved: 0,
+            failed: 0,
+        }
+    }
+}
+
+impl<S: GuessStrategy> Bot for GuesserBot<S> {
+    fn role(&self) -> BotRole {
+        BotRole::Guesser
+    }
+
+    fn on_match_began(&mut self) {
+        self.strategy.reset();
+    }
+
+    fn next_attempt(&mut self) -> Option<String> {
+        self.strategy.next_guess()
+    }
+
+    fn on_attempt_result(&mut self, attempt: &str, feedback: &AttemptFeedback) {
+        self.strategy.observe_feedback(attempt, feedback);
+    }
+
+    fn on_hint(&mut self, hint: &str) {
+        self.strategy.observe_hint(hint);
+    }
+
+    fn on_match_ended(&mut self, score: &MatchScore) {
+        match score.outcome {
+            MatchOutcome::Solved => self.solved += 1,
+            _ => self.failed += 1,
+        }
+    }
+}
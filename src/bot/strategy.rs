@@ -0,0 +1,93 @@
+use crate::server::{letter_feedback, AttemptFeedback, HIDDEN_LETTER};
+
+/// Decides which word a guesser bot tries next.
+pub trait GuessStrategy: Send {
+    /// Called when a new match begins.
+    fn reset(&mut self);
+
+    /// Next word to try, `None` when the strategy ran out of ideas.
+    fn next_guess(&mut self) -> Option<String>;
+
+    fn observe_feedback(&mut self, _attempt: &str, _feedback: &AttemptFeedback) {}
+
+    fn observe_hint(&mut self, _hint: &str) {}
+}
+
+/// Tries every word of the list in order.
+pub struct SequentialStrategy {
+    words: Vec<String>,
+    position: usize,
+}
+
+impl SequentialStrategy {
+    pub fn new(words: Vec<String>) -> SequentialStrategy {
+        SequentialStrategy { words, position: 0 }
+    }
+}
+
+impl GuessStrategy for SequentialStrategy {
+    fn reset(&mut self) {
+        self.position = 0;
+    }
+
+    fn next_guess(&mut self) -> Option<String> {
+        let word = self.words.get(self.position).cloned();
+        self.position += 1;
+        word
+    }
+}
+
+/// Tries words of the list that are still consistent with everything the bot learned in the match:
+/// per-letter feedback from `MatchMode::Wordle` matches and masked hints like "ap___".
+pub struct FilteringStrategy {
+    words: Vec<String>,
+    candidates: Vec<String>,
+}
+
+impl FilteringStrategy {
+    pub fn new(words: Vec<String>) -> FilteringStrategy {
+        let words: Vec<String> = words.into_iter().map(|w| w.to_lowercase()).collect();
+
+        FilteringStrategy {
+            candidates: words.clone(),
+            words,
+        }
+    }
+}
+
+impl GuessStrategy for FilteringStrategy {
+    fn reset(&mut self) {
+        self.candidates = self.words.clone();
+    }
+
+    fn next_guess(&mut self) -> Option<String> {
+        if self.candidates.is_empty() {
+            return None;
+        }
+        Some(self.candidates.remove(0))
+    }
+
+    fn observe_feedback(&mut self, attempt: &str, feedback: &AttemptFeedback) {
+        let attempt = attempt.to_lowercase();
+        self.candidates.retain(|c| *c != attempt);
+
+        if !feedback.letters.is_empty() {
+            self.candidates.retain(|c| letter_feedback(c, &attempt) == feedback.letters);
+        }
+    }
+
+    fn observe_hint(&mut self, hint: &str) {
+        let mask: Vec<char> = hint.to_lowercase().chars().collect();
+
+        // free text hints can't be used to filter
+        let is_mask = mask.contains(&HIDDEN_LETTER) && mask.iter().all(|c| *c == HIDDEN_LETTER || c.is_alphanumeric());
+        if !is_mask {
+            return;
+        }
+
+        self.candidates.retain(|c| {
+            let chars: Vec<char> = c.chars().collect();
+            chars.len() == mask.len() && chars.iter().zip(&mask).all(|(c, m)| *m == HIDDEN_LETTER || c == m)
+        });
+    }
+}
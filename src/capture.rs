@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::Duration;
+use anyhow::bail;
+use futures::channel::mpsc::UnboundedSender;
+use futures::{SinkExt, StreamExt};
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tokio_util::bytes::Bytes;
+use tokio_util::codec::{Framed, LengthDelimitedCodec};
+use crate::bin_reader::BinReader;
+use crate::bin_writer::BinWriter;
+use crate::client::ClientMessage;
+use crate::json::{authorized_to_json, client_message_to_json, server_message_to_json};
+use crate::server::ServerMessage;
+use crate::utils::{create_framed_stream, unix_millis, ClientId};
+
+/// Size of a record without its frame: timestamp, client id, kind and frame length.
+const RECORD_HEADER_LENGTH: usize = 15;
+
+/// How long a replay waits for a frame the capture says the server sent.
+const REPLAY_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(u8)]
+pub enum CaptureKind {
+    Connected,
+    Disconnected,
+    /// Frame the client sent to the server.
+    Inbound,
+    /// Frame the server sent to the client.
+    Outbound,
+}
+
+impl TryFrom<u8> for CaptureKind {
+    type Error = anyhow::Error;
+
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        match v {
+            x if x == CaptureKind::Connected as u8 => Ok(CaptureKind::Connected),
+            x if x == CaptureKind::Disconnected as u8 => Ok(CaptureKind::Disconnected),
+            x if x == CaptureKind::Inbound as u8 => Ok(CaptureKind::Inbound),
+            x if x == CaptureKind::Outbound as u8 => Ok(CaptureKind::Outbound),
+            _ => bail!("Couldn't convert {} to CaptureKind", v),
+        }
+    }
+}
+
+/// A single entry of a capture file, frames are empty for connection events.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptureRecord {
+    /// Milliseconds since the unix epoch.
+    pub timestamp: u64,
+    pub client_id: ClientId,
+    pub kind: CaptureKind,
+    pub frame: Vec<u8>,
+}
+
+impl CaptureRecord {
+    pub fn write(&self, bin_writer: &mut BinWriter) {
+        bin_writer.write_u64(self.timestamp);
+        bin_writer.write_u32(self.client_id);
+        bin_writer.write_u8(self.kind as u8);
+        bin_writer.write_u16(self.frame.len() as u16);
+        for byte in &self.frame {
+            bin_writer.write_u8(*byte);
+        }
+    }
+
+    pub fn read(bin_reader: &mut BinReader) -> anyhow::Result<CaptureRecord> {
+        let timestamp = bin_reader.read_u64()?;
+        let client_id = bin_reader.read_u32()?;
+        let kind = CaptureKind::try_from(bin_reader.read_u8()?)?;
+        let len = bin_reader.read_u16()? as usize;
+
+        Ok(CaptureRecord {
+            timestamp,
+            client_id,
+            kind,
+            frame: (0..len).map(|_| bin_reader.read_u8()).collect::<anyhow::Result<_>>()?,
+        })
+    }
+}
+
+/// Appends every frame the server receives and sends to a capture file. Passwords are blanked out
+/// of authorization and room frames before they are recorded.
+#[derive(Clone)]
+pub struct CaptureWriter {
+    sender: UnboundedSender<Vec<u8>>,
+}
+
+impl CaptureWriter {
+    /// Opens the file and spawns the task writing to it, records are written in the order they
+    /// were made.
+    pub fn create(path: &Path) -> anyhow::Result<CaptureWriter> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let mut file = tokio::fs::File::from_std(file);
+        let (sender, mut receiver) = futures::channel::mpsc::unbounded::<Vec<u8>>();
+
+        tokio::spawn(async move {
+            while let Some(record) = receiver.next().await {
+                let result = match file.write_all(&record).await {
+                    Ok(()) => file.flush().await,
+                    Err(err) => Err(err),
+                };
+                if let Err(err) = result {
+                    eprintln!("Couldn't write capture record: {}", err);
+                }
+            }
+        });
+
+        Ok(CaptureWriter { sender })
+    }
+
+    pub fn record(&self, client_id: ClientId, kind: CaptureKind, frame: &[u8]) {
+        let frame = match kind {
+            CaptureKind::Inbound => redact_passwords(frame),
+            _ => frame.to_vec(),
+        };
+        let record = CaptureRecord { timestamp: unix_millis(), client_id, kind, frame };
+        let mut bin_writer = BinWriter::with_capacity(RECORD_HEADER_LENGTH + record.frame.len());
+        record.write(&mut bin_writer);
+
+        if self.sender.unbounded_send(bin_writer.clone_data()).is_err() {
+            eprintln!("Couldn't write capture record: the capture task stopped");
+        }
+    }
+}
+
+/// Passwords a replay sends in place of the ones blanked out of the capture.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReplayPasswords {
+    pub server: String,
+    pub admin: String,
+    /// Sent for every named player, the accounts are registered with it on a fresh server.
+    pub account: String,
+    /// Sent when rooms are created and joined, the captured rooms are created with it again.
+    pub room: String,
+}
+
+/// Blanks out the passwords of authorization and room frames, frames that can't be read are cut to
+/// their flag.
+fn redact_passwords(frame: &[u8]) -> Vec<u8> {
+    replace_passwords(frame, &ReplayPasswords::default()).unwrap_or_else(|_| frame.iter().take(1).copied().collect())
+}
+
+/// Puts the passwords in place of the ones a frame carries, frames without passwords are returned
+/// as they are.
+fn replace_passwords(frame: &[u8], passwords: &ReplayPasswords) -> anyhow::Result<Vec<u8>> {
+    let mut bin_reader = BinReader::from_slice(frame);
+    let mut bin_writer = BinWriter::with_capacity(frame.len() + passwords.server.len() + passwords.account.len());
+
+    match frame.first().map(|flag| ClientMessage::try_from(*flag)) {
+        Some(Ok(ClientMessage::Authorization)) => {
+            bin_writer.write_u8(bin_reader.read_u8()?);
+            bin_reader.read_str()?;
+            let name = bin_reader.read_str()?;
+            bin_reader.read_str()?;
+
+            bin_writer.write_str(&passwords.server);
+            bin_writer.write_str(&name);
+            bin_writer.write_str(&passwords.account);
+        }
+        Some(Ok(ClientMessage::AdminAuthorization)) => {
+            bin_writer.write_u8(bin_reader.read_u8()?);
+            bin_reader.read_str()?;
+
+            bin_writer.write_str(&passwords.admin);
+        }
+        Some(Ok(ClientMessage::CreateRoom | ClientMessage::JoinRoom)) => {
+            bin_writer.write_u8(bin_reader.read_u8()?);
+            let name = bin_reader.read_str()?;
+            bin_reader.read_str()?;
+
+            bin_writer.write_str(&name);
+            bin_writer.write_str(&passwords.room);
+        }
+        _ => return Ok(frame.to_vec()),
+    }
+
+    Ok(bin_writer.clone_data())
+}
+
+pub fn read_capture(path: &Path) -> anyhow::Result<Vec<CaptureRecord>> {
+    parse_capture(&std::fs::read(path)?)
+}
+
+pub fn parse_capture(data: &[u8]) -> anyhow::Result<Vec<CaptureRecord>> {
+    let mut bin_reader = BinReader::from_slice(data);
+
+    let mut records = Vec::new();
+    while bin_reader.remaining() > 0 {
+        records.push(CaptureRecord::read(&mut bin_reader)?);
+    }
+    Ok(records)
+}
+
+/// One line per record with the frames decoded to their JSON form.
+pub fn describe_capture(records: &[CaptureRecord]) -> Vec<String> {
+    // the id sent after authorization is the only frame without a flag
+    let mut awaiting_id: HashMap<ClientId, bool> = HashMap::new();
+
+    records
+        .iter()
+        .map(|record| {
+            let message = match record.kind {
+                CaptureKind::Connected | CaptureKind::Disconnected => Ok(String::new()),
+                CaptureKind::Inbound => client_message_to_json(&record.frame),
+                CaptureKind::Outbound => {
+                    let awaiting = awaiting_id.entry(record.client_id).or_default();
+                    if *awaiting && record.frame.len() == 4 {
+                        *awaiting = false;
+                        BinReader::from_slice(&record.frame).read_u32().map(authorized_to_json)
+                    } else {
+                        *awaiting |= record.frame.first() == Some(&(ServerMessage::RequestAuthorization as u8));
+                        server_message_to_json(&record.frame)
+                    }
+                }
+            };
+
+            let message = message.unwrap_or_else(|err| format!("{:?} ({})", record.frame, err));
+            format!("{} client {} {:?} {}", record.timestamp, record.client_id, record.kind, message).trim_end().to_string()
+        })
+        .collect()
+}
+
+/// A frame the server sent during a replay that differs from the captured one, `None` when it
+/// never arrived.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayMismatch {
+    /// Index of the outbound record in the capture.
+    pub record: usize,
+    pub expected: Vec<u8>,
+    pub actual: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReplayReport {
+    pub frames_sent: usize,
+    pub frames_compared: usize,
+    pub mismatches: Vec<ReplayMismatch>,
+}
+
+/// Plays the clients of a capture against a fresh server and compares its frames with the captured
+/// ones. Clients connect in the captured order, so they get the same ids when the capture also
+/// started on a fresh server. Captures don't contain passwords, `passwords` are sent in their place.
+pub async fn replay_capture_async(
+    records: &[CaptureRecord],
+    addr: SocketAddr,
+    passwords: &ReplayPasswords,
+) -> anyhow::Result<ReplayReport> {
+    let mut streams: HashMap<ClientId, Framed<TcpStream, LengthDelimitedCodec>> = HashMap::new();
+    let mut report = ReplayReport::default();
+
+    for (index, record) in records.iter().enumerate() {
+        match record.kind {
+            CaptureKind::Connected => {
+                streams.insert(record.client_id, create_framed_stream(TcpStream::connect(addr).await?));
+            }
+            CaptureKind::Disconnected => {
+                streams.remove(&record.client_id);
+            }
+            CaptureKind::Inbound => {
+                if let Some(stream) = streams.get_mut(&record.client_id) {
+                    let frame = replace_passwords(&record.frame, passwords).unwrap_or_else(|_| record.frame.clone());
+                    stream.send(Bytes::from(frame)).await?;
+                    report.frames_sent += 1;
+                }
+            }
+            CaptureKind::Outbound => {
+                let Some(stream) = streams.get_mut(&record.client_id) else {
+                    continue;
+                };
+
+                let actual = match timeout(REPLAY_TIMEOUT, stream.next()).await {
+                    Ok(Some(Ok(frame))) => Some(frame.to_vec()),
+                    _ => None,
+                };
+                report.frames_compared += 1;
+                if actual.as_ref() != Some(&record.frame) {
+                    report.mismatches.push(ReplayMismatch { record: index, expected: record.frame.clone(), actual });
+                }
+            }
+        }
+    }
+
+    Ok(report)
+}
@@ -7,7 +7,28 @@ pub enum ClientMessage {
     ListOfOpponents,
     RequestMatch,
     SendHint,
-    SendAttempt
+    SendAttempt,
+    MatchHistory,
+    Leaderboard,
+    GuessLetter,
+    RequestSoloMatch,
+    SendChat,
+    SendDirectMessage,
+    ListOfRooms,
+    CreateRoom,
+    JoinRoom,
+    RequestRace,
+    RequestTournament,
+    SendTournamentWord,
+    AdminAuthorization,
+    ClearLockout,
+    ListOfConnections,
+    ListOfMatches,
+    Kick,
+    EndMatch,
+    Broadcast,
+    Ban,
+    Unban
 }
 
 impl TryFrom<u8> for ClientMessage {
@@ -20,6 +41,27 @@ impl TryFrom<u8> for ClientMessage {
             x if x == ClientMessage::RequestMatch as u8 => Ok(ClientMessage::RequestMatch),
             x if x == ClientMessage::SendHint as u8 => Ok(ClientMessage::SendHint),
             x if x == ClientMessage::SendAttempt as u8 => Ok(ClientMessage::SendAttempt),
+            x if x == ClientMessage::MatchHistory as u8 => Ok(ClientMessage::MatchHistory),
+            x if x == ClientMessage::Leaderboard as u8 => Ok(ClientMessage::Leaderboard),
+            x if x == ClientMessage::GuessLetter as u8 => Ok(ClientMessage::GuessLetter),
+            x if x == ClientMessage::RequestSoloMatch as u8 => Ok(ClientMessage::RequestSoloMatch),
+            x if x == ClientMessage::SendChat as u8 => Ok(ClientMessage::SendChat),
+            x if x == ClientMessage::SendDirectMessage as u8 => Ok(ClientMessage::SendDirectMessage),
+            x if x == ClientMessage::ListOfRooms as u8 => Ok(ClientMessage::ListOfRooms),
+            x if x == ClientMessage::CreateRoom as u8 => Ok(ClientMessage::CreateRoom),
+            x if x == ClientMessage::JoinRoom as u8 => Ok(ClientMessage::JoinRoom),
+            x if x == ClientMessage::RequestRace as u8 => Ok(ClientMessage::RequestRace),
+            x if x == ClientMessage::RequestTournament as u8 => Ok(ClientMessage::RequestTournament),
+            x if x == ClientMessage::SendTournamentWord as u8 => Ok(ClientMessage::SendTournamentWord),
+            x if x == ClientMessage::AdminAuthorization as u8 => Ok(ClientMessage::AdminAuthorization),
+            x if x == ClientMessage::ClearLockout as u8 => Ok(ClientMessage::ClearLockout),
+            x if x == ClientMessage::ListOfConnections as u8 => Ok(ClientMessage::ListOfConnections),
+            x if x == ClientMessage::ListOfMatches as u8 => Ok(ClientMessage::ListOfMatches),
+            x if x == ClientMessage::Kick as u8 => Ok(ClientMessage::Kick),
+            x if x == ClientMessage::EndMatch as u8 => Ok(ClientMessage::EndMatch),
+            x if x == ClientMessage::Broadcast as u8 => Ok(ClientMessage::Broadcast),
+            x if x == ClientMessage::Ban as u8 => Ok(ClientMessage::Ban),
+            x if x == ClientMessage::Unban as u8 => Ok(ClientMessage::Unban),
             _ => bail!("Couldn't convert {} to ClientMessage", v),
         }
     }
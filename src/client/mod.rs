@@ -1,23 +1,36 @@
-use std::net::SocketAddr;
+use std::collections::VecDeque;
+use std::net::{IpAddr, SocketAddr};
+#[cfg(unix)]
+use std::path::Path;
 use std::time::Duration;
 use tokio_util::bytes::Bytes;
 use anyhow::bail;
-use futures::{SinkExt, StreamExt, TryStreamExt};
+use futures::{SinkExt, StreamExt};
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::{TcpStream};
+#[cfg(unix)]
+use tokio::net::UnixStream;
 use tokio::time::timeout;
 use tokio_util::codec::{Framed, LengthDelimitedCodec};
 use crate::bin_reader::BinReader;
 use crate::bin_writer::{BinWriter};
-use crate::utils::{create_framed_stream, ClientId, MAX_PACKET_LENGTH};
+use crate::tls::ClientTlsConfig;
+use crate::utils::{create_framed_stream, BoxedStream, ClientId, MAX_PACKET_LENGTH};
 
 mod message;
 pub use message::ClientMessage;
-use crate::server::ServerMessage;
+use crate::server::{
+    read_letters, AttemptFeedback, Ban, ChatMessage, ConnectionInfo, ErrorCode, GameError, HangmanState, LeaderboardEntry, LetterGuess, MatchInfo, MatchMode,
+    MatchScore, MatchSummary, RaceStanding, RoomInfo, ServerMessage, TournamentFormat, TournamentPairing,
+    WordSource, SERVER_ID
+};
 
 pub struct Client {
     pub id: ClientId,
-    stream: Framed<TcpStream, LengthDelimitedCodec>,
-    bin_writer: BinWriter
+    stream: Framed<BoxedStream, LengthDelimitedCodec>,
+    bin_writer: BinWriter,
+    // streamed messages received while waiting for a response
+    streamed: VecDeque<StreamedMessage>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -25,18 +38,75 @@ pub enum StreamedMessage {
     Challenged,
     Hint(String),
     Attempt(bool, String),
+    /// Attempt in a `MatchMode::Wordle` match.
+    AttemptWithFeedback(AttemptFeedback, String),
+    MatchEnded(MatchScore),
+    /// Masked word at the start of a `MatchMode::Hangman` match.
+    HangmanState(HangmanState),
+    /// Letter the guesser tried in a `MatchMode::Hangman` match.
+    LetterGuessed(String, LetterGuess),
+    Chat(ChatMessage),
+    /// Final standings of a race, winner first.
+    RaceStandings(Vec<RaceStanding>),
+    /// Every pairing of the tournament, sent whenever one of them changes.
+    TournamentBracket(Vec<TournamentPairing>),
+    /// The server waits for the word of the tournament match against the given opponent.
+    TournamentWordRequested(ClientId),
+    /// The tournament is over, `None` when every player left.
+    TournamentWinner(Option<ClientId>),
 }
 
 impl Client {
     pub async fn connect_async(addr: SocketAddr, password: &str) -> anyhow::Result<Client> {
+        Self::connect_as_async(addr, password, "", "").await
+    }
+
+    /// Connects with an account name, ratings and history of named players persist across sessions.
+    /// The account is registered with `account_password` the first time the name is used.
+    pub async fn connect_as_async(addr: SocketAddr, password: &str, name: &str, account_password: &str) -> anyhow::Result<Client> {
         let stream = TcpStream::connect(addr).await?;
+        Self::connect_stream_async(stream, password, name, account_password).await
+    }
+
+    /// Connects over the server's Unix domain socket.
+    #[cfg(unix)]
+    pub async fn connect_unix_async<P: AsRef<Path>>(
+        path: P,
+        password: &str,
+        name: &str,
+        account_password: &str) -> anyhow::Result<Client> {
+        let stream = UnixStream::connect(path).await?;
+        Self::connect_stream_async(stream, password, name, account_password).await
+    }
+
+    /// Connects over an already open stream, such as a `MemoryConnector` pipe or a custom tunnel.
+    pub async fn connect_stream_async<S>(stream: S, password: &str, name: &str, account_password: &str) -> anyhow::Result<Client>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        Self::authorize_async(Box::new(stream), password, name, account_password).await
+    }
+
+    /// Connects to a server that requires TLS.
+    pub async fn connect_tls_async(
+        addr: SocketAddr,
+        tls: &ClientTlsConfig,
+        password: &str,
+        name: &str,
+        account_password: &str) -> anyhow::Result<Client> {
+        let stream = TcpStream::connect(addr).await?;
+        let stream = tls.connector()?.connect(tls.server_name()?, stream).await?;
+        Self::connect_stream_async(stream, password, name, account_password).await
+    }
+
+    async fn authorize_async(stream: BoxedStream, password: &str, name: &str, account_password: &str) -> anyhow::Result<Client> {
         let mut framed_stream = create_framed_stream(stream);
 
         // expect request authorization message from the server
         let result = framed_stream.next().await;
         let mut reader = BinReader::from_result(&result)?;
 
-        let message: ServerMessage = ServerMessage::try_from(reader.read_u8())?;
+        let message: ServerMessage = ServerMessage::try_from(reader.read_u8()?)?;
         if message != ServerMessage::RequestAuthorization {
             bail!("Unexpected server message '{:?}'", message);
         }
@@ -45,6 +115,8 @@ impl Client {
         let mut bin_writer = BinWriter::with_capacity(MAX_PACKET_LENGTH);
         bin_writer.write_u8(ClientMessage::Authorization as u8);
         bin_writer.write_str(password);
+        bin_writer.write_str(name);
+        bin_writer.write_str(account_password);
 
         framed_stream.send(Bytes::from(bin_writer.clone_data())).await?;
 
@@ -52,10 +124,16 @@ impl Client {
         let result = framed_stream.next().await;
         let mut reader = BinReader::from_result(&result)?;
 
+        // an error is two bytes long, unlike the id
+        if reader.remaining() == 2 && ServerMessage::try_from(reader.read_u8()?)? == ServerMessage::Err {
+            bail!(server_error(&mut reader, "Couldn't authorize"));
+        }
+
         Ok(Client{
-            id: reader.read_u32(),
+            id: reader.read_u32()?,
             stream: framed_stream,
-            bin_writer
+            bin_writer,
+            streamed: VecDeque::new(),
         })
     }
 
@@ -68,46 +146,116 @@ impl Client {
         self.stream.send(Bytes::from(self.bin_writer.clone_data())).await?;
 
         // receive response
-        let result = self.stream.next().await;
-        let mut reader = BinReader::from_result(&result)?;
+        let response = self.read_response_async().await?;
+        let mut reader = BinReader::from_bytes(&response);
 
-        let message = ServerMessage::try_from(reader.read_u8())?;
+        let message = ServerMessage::try_from(reader.read_u8()?)?;
         Ok(match message {
             ServerMessage::ListOfOpponents => {
-                let opponents_count = reader.read_u16() as usize;
+                let opponents_count = reader.read_u16()? as usize;
                 let mut opponents = Vec::with_capacity(opponents_count);
 
                 for _ in 0..opponents_count {
-                    opponents.push(reader.read_u32());
+                    opponents.push(reader.read_u32()?);
                 }
 
                 opponents
             },
+            ServerMessage::Err => bail!(server_error(&mut reader, "Couldn't fetch opponents")),
             _ => bail!("Unexpected server message '{:?}'", message)
         })
     }
 
     pub async fn request_match_async(&mut self, opponent_id: ClientId, word: &str) -> anyhow::Result<()> {
+        self.request_match_with_mode_async(opponent_id, word, MatchMode::Classic).await
+    }
+
+    pub async fn request_match_with_mode_async(
+        &mut self,
+        opponent_id: ClientId,
+        word: &str,
+        mode: MatchMode) -> anyhow::Result<()> {
         self.bin_writer.clear();
 
         // send request
         self.bin_writer.write_u8(ClientMessage::RequestMatch as u8);
         self.bin_writer.write_u32(opponent_id);
         self.bin_writer.write_str(word);
+        self.bin_writer.write_u8(mode as u8);
         self.stream.send(Bytes::from(self.bin_writer.clone_data())).await?;
 
-        // receive response
-        let result = self.stream.next().await;
-        let mut reader = BinReader::from_result(&result)?;
+        self.read_ok_async("Couldn't begin match with opponent").await
+    }
 
-        let message = ServerMessage::try_from(reader.read_u8())?;
-        match message {
-            ServerMessage::Ok => {},
-            ServerMessage::Err => bail!("Couldn't begin match with opponent"),
-            _ => bail!("Unexpected server message '{:?}'", message)
+    /// Opens a match to several opponents at once, the first one to guess the word wins.
+    pub async fn request_race_async(&mut self, opponent_ids: &[ClientId], word: &str, mode: MatchMode) -> anyhow::Result<()> {
+        self.bin_writer.clear();
+
+        // send request
+        self.bin_writer.write_u8(ClientMessage::RequestRace as u8);
+        self.bin_writer.write_str(word);
+        self.bin_writer.write_u8(mode as u8);
+        self.bin_writer.write_u16(opponent_ids.len() as u16);
+        for id in opponent_ids {
+            self.bin_writer.write_u32(*id);
+        }
+        self.stream.send(Bytes::from(self.bin_writer.clone_data())).await?;
+
+        self.read_ok_async("Couldn't begin race").await
+    }
+
+    /// Runs a tournament among players of the room, the server starts the matches as players get free.
+    pub async fn request_tournament_async(
+        &mut self,
+        player_ids: &[ClientId],
+        format: TournamentFormat,
+        words: WordSource,
+        mode: MatchMode) -> anyhow::Result<()> {
+        self.bin_writer.clear();
+
+        // send request
+        self.bin_writer.write_u8(ClientMessage::RequestTournament as u8);
+        self.bin_writer.write_u8(format as u8);
+        self.bin_writer.write_u8(words as u8);
+        self.bin_writer.write_u8(mode as u8);
+        self.bin_writer.write_u16(player_ids.len() as u16);
+        for id in player_ids {
+            self.bin_writer.write_u32(*id);
+        }
+        self.stream.send(Bytes::from(self.bin_writer.clone_data())).await?;
+
+        self.read_ok_async("Couldn't begin tournament").await
+    }
+
+    /// Sends the word of a tournament match after `StreamedMessage::TournamentWordRequested`.
+    pub async fn send_tournament_word_async(&mut self, word: &str) -> anyhow::Result<()> {
+        self.bin_writer.clear();
+
+        // send request
+        self.bin_writer.write_u8(ClientMessage::SendTournamentWord as u8);
+        self.bin_writer.write_str(word);
+        self.stream.send(Bytes::from(self.bin_writer.clone_data())).await?;
+
+        self.read_ok_async("Couldn't send tournament word").await
+    }
+
+    /// Starts a match against the server, which picks the word. The same seed picks the same word.
+    pub async fn request_solo_match_async(&mut self, mode: MatchMode, seed: Option<u64>) -> anyhow::Result<()> {
+        self.bin_writer.clear();
+
+        // send request
+        self.bin_writer.write_u8(ClientMessage::RequestSoloMatch as u8);
+        self.bin_writer.write_u8(mode as u8);
+        match seed {
+            Some(seed) => {
+                self.bin_writer.write_u8(1);
+                self.bin_writer.write_u64(seed);
+            }
+            None => self.bin_writer.write_u8(0),
         }
+        self.stream.send(Bytes::from(self.bin_writer.clone_data())).await?;
 
-        Ok(())
+        self.read_ok_async("Couldn't begin solo match").await
     }
 
     pub async fn send_hint_async(&mut self, hint: &str) -> anyhow::Result<()> {
@@ -118,10 +266,15 @@ impl Client {
         self.bin_writer.write_str(hint);
         self.stream.send(Bytes::from(self.bin_writer.clone_data())).await?;
 
-        Ok(())
+        self.read_ok_async("Couldn't send hint").await
     }
 
     pub async fn send_attempt_async(&mut self, word: &str) -> anyhow::Result<bool> {
+        Ok(self.send_attempt_with_feedback_async(word).await?.correct)
+    }
+
+    /// Sends an attempt and returns the per-letter feedback in `MatchMode::Wordle` matches.
+    pub async fn send_attempt_with_feedback_async(&mut self, word: &str) -> anyhow::Result<AttemptFeedback> {
         self.bin_writer.clear();
 
         // send request
@@ -130,32 +283,327 @@ impl Client {
         self.stream.send(Bytes::from(self.bin_writer.clone_data())).await?;
 
         // receive response
-        let result = self.stream.next().await;
-        let mut reader = BinReader::from_result(&result)?;
+        let response = self.read_response_async().await?;
+        let mut reader = BinReader::from_bytes(&response);
+
+        let message = ServerMessage::try_from(reader.read_u8()?)?;
+        match message {
+            ServerMessage::AttemptResult => AttemptFeedback::read(&mut reader),
+            ServerMessage::Err => bail!(server_error(&mut reader, "Couldn't send attempt")),
+            _ => bail!("Unexpected server message '{:?}'", message)
+        }
+    }
+
+    /// Guesses a single letter in a `MatchMode::Hangman` match.
+    pub async fn guess_letter_async(&mut self, letter: char) -> anyhow::Result<LetterGuess> {
+        self.bin_writer.clear();
+
+        // send request
+        self.bin_writer.write_u8(ClientMessage::GuessLetter as u8);
+        self.bin_writer.write_str(letter.encode_utf8(&mut [0; 4]));
+        self.stream.send(Bytes::from(self.bin_writer.clone_data())).await?;
+
+        // receive response
+        let response = self.read_response_async().await?;
+        let mut reader = BinReader::from_bytes(&response);
+
+        let message = ServerMessage::try_from(reader.read_u8()?)?;
+        match message {
+            ServerMessage::LetterResult => LetterGuess::read(&mut reader),
+            ServerMessage::Err => bail!(server_error(&mut reader, "Couldn't guess letter")),
+            _ => bail!("Unexpected server message '{:?}'", message)
+        }
+    }
+
+    /// Sends a chat message to everyone in the room.
+    pub async fn send_chat_async(&mut self, text: &str) -> anyhow::Result<()> {
+        self.bin_writer.clear();
+
+        // send request
+        self.bin_writer.write_u8(ClientMessage::SendChat as u8);
+        self.bin_writer.write_str(text);
+        self.stream.send(Bytes::from(self.bin_writer.clone_data())).await?;
+
+        self.read_ok_async("Couldn't send chat message").await
+    }
+
+    /// Sends a chat message only to the given player.
+    pub async fn send_direct_message_async(&mut self, recipient_id: ClientId, text: &str) -> anyhow::Result<()> {
+        self.bin_writer.clear();
+
+        // send request
+        self.bin_writer.write_u8(ClientMessage::SendDirectMessage as u8);
+        self.bin_writer.write_u32(recipient_id);
+        self.bin_writer.write_str(text);
+        self.stream.send(Bytes::from(self.bin_writer.clone_data())).await?;
+
+        self.read_ok_async("Couldn't send direct message").await
+    }
+
+    /// Fetches the most recent finished matches this client took part in, newest first.
+    pub async fn get_match_history_async(&mut self, limit: u16) -> anyhow::Result<Vec<MatchSummary>> {
+        self.bin_writer.clear();
+
+        // send request
+        self.bin_writer.write_u8(ClientMessage::MatchHistory as u8);
+        self.bin_writer.write_u16(limit);
+        self.stream.send(Bytes::from(self.bin_writer.clone_data())).await?;
 
-        let message = ServerMessage::try_from(reader.read_u8())?;
+        // receive response
+        let response = self.read_response_async().await?;
+        let mut reader = BinReader::from_bytes(&response);
+
+        let message = ServerMessage::try_from(reader.read_u8()?)?;
+        match message {
+            ServerMessage::MatchHistory => {
+                let count = reader.read_u16()? as usize;
+                let mut matches = Vec::with_capacity(count);
+
+                for _ in 0..count {
+                    matches.push(MatchSummary::read(&mut reader)?);
+                }
+
+                Ok(matches)
+            },
+            ServerMessage::Err => bail!(server_error(&mut reader, "Couldn't fetch match history")),
+            _ => bail!("Unexpected server message '{:?}'", message)
+        }
+    }
+
+    /// Fetches the highest rated players.
+    pub async fn get_leaderboard_async(&mut self, count: u16) -> anyhow::Result<Vec<LeaderboardEntry>> {
+        self.bin_writer.clear();
+
+        // send request
+        self.bin_writer.write_u8(ClientMessage::Leaderboard as u8);
+        self.bin_writer.write_u16(count);
+        self.stream.send(Bytes::from(self.bin_writer.clone_data())).await?;
+
+        // receive response
+        let response = self.read_response_async().await?;
+        let mut reader = BinReader::from_bytes(&response);
+
+        let message = ServerMessage::try_from(reader.read_u8()?)?;
         match message {
-            ServerMessage::AttemptResult => {
-                Ok(reader.read_u8() > 0)
+            ServerMessage::Leaderboard => {
+                let count = reader.read_u16()? as usize;
+                let mut entries = Vec::with_capacity(count);
+
+                for _ in 0..count {
+                    entries.push(LeaderboardEntry {
+                        name: reader.read_str()?,
+                        rating: reader.read_u32()?,
+                    });
+                }
+
+                Ok(entries)
             },
+            ServerMessage::Err => bail!(server_error(&mut reader, "Couldn't fetch leaderboard")),
             _ => bail!("Unexpected server message '{:?}'", message)
         }
     }
 
+    /// Lists the rooms players can join.
+    pub async fn get_rooms_async(&mut self) -> anyhow::Result<Vec<RoomInfo>> {
+        self.bin_writer.clear();
+
+        // send request
+        self.bin_writer.write_u8(ClientMessage::ListOfRooms as u8);
+        self.stream.send(Bytes::from(self.bin_writer.clone_data())).await?;
+
+        // receive response
+        let response = self.read_response_async().await?;
+        let mut reader = BinReader::from_bytes(&response);
+
+        let message = ServerMessage::try_from(reader.read_u8()?)?;
+        match message {
+            ServerMessage::ListOfRooms => {
+                let count = reader.read_u16()? as usize;
+                let mut rooms = Vec::with_capacity(count);
+
+                for _ in 0..count {
+                    rooms.push(RoomInfo::read(&mut reader)?);
+                }
+
+                Ok(rooms)
+            },
+            ServerMessage::Err => bail!(server_error(&mut reader, "Couldn't fetch rooms")),
+            _ => bail!("Unexpected server message '{:?}'", message)
+        }
+    }
+
+    /// Creates a room with the server's rules and moves into it, an empty password leaves it open.
+    pub async fn create_room_async(&mut self, name: &str, password: &str) -> anyhow::Result<()> {
+        self.bin_writer.clear();
+
+        // send request
+        self.bin_writer.write_u8(ClientMessage::CreateRoom as u8);
+        self.bin_writer.write_str(name);
+        self.bin_writer.write_str(password);
+        self.stream.send(Bytes::from(self.bin_writer.clone_data())).await?;
+
+        self.read_ok_async("Couldn't create room").await
+    }
+
+    /// Leaves the current room for another one, the password is ignored by open rooms.
+    pub async fn join_room_async(&mut self, name: &str, password: &str) -> anyhow::Result<()> {
+        self.bin_writer.clear();
+
+        // send request
+        self.bin_writer.write_u8(ClientMessage::JoinRoom as u8);
+        self.bin_writer.write_str(name);
+        self.bin_writer.write_str(password);
+        self.stream.send(Bytes::from(self.bin_writer.clone_data())).await?;
+
+        self.read_ok_async("Couldn't join room").await
+    }
+
+    /// Sends the server's admin password, admin commands fail until it's accepted.
+    pub async fn authorize_admin_async(&mut self, password: &str) -> anyhow::Result<()> {
+        self.bin_writer.clear();
+
+        // send request
+        self.bin_writer.write_u8(ClientMessage::AdminAuthorization as u8);
+        self.bin_writer.write_str(password);
+        self.stream.send(Bytes::from(self.bin_writer.clone_data())).await?;
+
+        self.read_ok_async("Couldn't authorize as admin").await
+    }
+
+    /// Lets an IP that was locked out for wrong passwords authorize again, requires admin.
+    pub async fn clear_lockout_async(&mut self, ip: IpAddr) -> anyhow::Result<()> {
+        self.bin_writer.clear();
+
+        // send request
+        self.bin_writer.write_u8(ClientMessage::ClearLockout as u8);
+        self.bin_writer.write_str(&ip.to_string());
+        self.stream.send(Bytes::from(self.bin_writer.clone_data())).await?;
+
+        self.read_ok_async("Couldn't clear lockout").await
+    }
+
+    /// Every connection on the server, requires admin.
+    pub async fn get_connections_async(&mut self) -> anyhow::Result<Vec<ConnectionInfo>> {
+        self.bin_writer.clear();
+
+        // send request
+        self.bin_writer.write_u8(ClientMessage::ListOfConnections as u8);
+        self.stream.send(Bytes::from(self.bin_writer.clone_data())).await?;
+
+        // receive response
+        let response = self.read_response_async().await?;
+        let mut reader = BinReader::from_bytes(&response);
+
+        let message = ServerMessage::try_from(reader.read_u8()?)?;
+        match message {
+            ServerMessage::ListOfConnections => {
+                let count = reader.read_u16()? as usize;
+                (0..count).map(|_| ConnectionInfo::read(&mut reader)).collect()
+            },
+            ServerMessage::Err => bail!(server_error(&mut reader, "Couldn't fetch connections")),
+            _ => bail!("Unexpected server message '{:?}'", message)
+        }
+    }
+
+    /// Matches being played in every room, requires admin.
+    pub async fn get_matches_async(&mut self) -> anyhow::Result<Vec<MatchInfo>> {
+        self.bin_writer.clear();
+
+        // send request
+        self.bin_writer.write_u8(ClientMessage::ListOfMatches as u8);
+        self.stream.send(Bytes::from(self.bin_writer.clone_data())).await?;
+
+        // receive response
+        let response = self.read_response_async().await?;
+        let mut reader = BinReader::from_bytes(&response);
+
+        let message = ServerMessage::try_from(reader.read_u8()?)?;
+        match message {
+            ServerMessage::ListOfMatches => {
+                let count = reader.read_u16()? as usize;
+                (0..count).map(|_| MatchInfo::read(&mut reader)).collect()
+            },
+            ServerMessage::Err => bail!(server_error(&mut reader, "Couldn't fetch matches")),
+            _ => bail!("Unexpected server message '{:?}'", message)
+        }
+    }
+
+    /// Closes the player's connection, requires admin.
+    pub async fn kick_async(&mut self, id: ClientId) -> anyhow::Result<()> {
+        self.bin_writer.clear();
+
+        // send request
+        self.bin_writer.write_u8(ClientMessage::Kick as u8);
+        self.bin_writer.write_u32(id);
+        self.stream.send(Bytes::from(self.bin_writer.clone_data())).await?;
+
+        self.read_ok_async("Couldn't kick player").await
+    }
+
+    /// Ends the match the guesser is in as `MatchOutcome::Aborted`, requires admin.
+    pub async fn end_match_async(&mut self, opponent_id: ClientId) -> anyhow::Result<()> {
+        self.bin_writer.clear();
+
+        // send request
+        self.bin_writer.write_u8(ClientMessage::EndMatch as u8);
+        self.bin_writer.write_u32(opponent_id);
+        self.stream.send(Bytes::from(self.bin_writer.clone_data())).await?;
+
+        self.read_ok_async("Couldn't end match").await
+    }
+
+    /// Sends a chat message from `SERVER_ID` to everyone in every room, requires admin.
+    pub async fn broadcast_async(&mut self, text: &str) -> anyhow::Result<()> {
+        self.bin_writer.clear();
+
+        // send request
+        self.bin_writer.write_u8(ClientMessage::Broadcast as u8);
+        self.bin_writer.write_str(text);
+        self.stream.send(Bytes::from(self.bin_writer.clone_data())).await?;
+
+        self.read_ok_async("Couldn't broadcast message").await
+    }
+
+    /// Bans an account or IP until the server restarts and kicks matching players, requires admin.
+    pub async fn ban_async(&mut self, ban: &Ban) -> anyhow::Result<()> {
+        self.bin_writer.clear();
+
+        // send request
+        self.bin_writer.write_u8(ClientMessage::Ban as u8);
+        ban.write(&mut self.bin_writer);
+        self.stream.send(Bytes::from(self.bin_writer.clone_data())).await?;
+
+        self.read_ok_async("Couldn't ban").await
+    }
+
+    /// Lifts a ban, requires admin.
+    pub async fn unban_async(&mut self, ban: &Ban) -> anyhow::Result<()> {
+        self.bin_writer.clear();
+
+        // send request
+        self.bin_writer.write_u8(ClientMessage::Unban as u8);
+        ban.write(&mut self.bin_writer);
+        self.stream.send(Bytes::from(self.bin_writer.clone_data())).await?;
+
+        self.read_ok_async("Couldn't lift ban").await
+    }
+
     pub async fn read_streamed_message_async(&mut self) -> anyhow::Result<Option<StreamedMessage>> {
+        if let Some(message) = self.streamed.pop_front() {
+            return Ok(Some(message));
+        }
+
         let duration = Duration::from_millis(50);
 
         match timeout(duration, self.stream.next()).await {
             Ok(result) => {
                 let mut reader = BinReader::from_result(&result)?;
 
-                let message = ServerMessage::try_from(reader.read_u8())?;
-                return Ok(match message {
-                    ServerMessage::Challenged => Some(StreamedMessage::Challenged),
-                    ServerMessage::Attempt => Some(StreamedMessage::Attempt(reader.read_u8() > 0, reader.read_str())),
-                    ServerMessage::Hint => Some(StreamedMessage::Hint(reader.read_str())),
-                    _ => bail!("Unexpected server message '{:?}'", message)
-                })
+                let message = ServerMessage::try_from(reader.read_u8()?)?;
+                return match read_streamed_message(message, &mut reader)? {
+                    Some(streamed) => Ok(Some(streamed)),
+                    None => bail!("Unexpected server message '{:?}'", message)
+                }
             }
             Err(_) => {
                 // read timed out
@@ -164,4 +612,92 @@ impl Client {
 
         Ok(None)
     }
-}
\ No newline at end of file
+
+    /// Reads a response that is either `Ok` or `Err` with a code.
+    async fn read_ok_async(&mut self, error: &str) -> anyhow::Result<()> {
+        let response = self.read_response_async().await?;
+        let mut reader = BinReader::from_bytes(&response);
+
+        let message = ServerMessage::try_from(reader.read_u8()?)?;
+        match message {
+            ServerMessage::Ok => Ok(()),
+            ServerMessage::Err => bail!(server_error(&mut reader, error)),
+            _ => bail!("Unexpected server message '{:?}'", message)
+        }
+    }
+
+    /// Reads the response to the last request. Streamed messages that arrive in the meantime
+    /// are queued for `read_streamed_message_async`.
+    async fn read_response_async(&mut self) -> anyhow::Result<Bytes> {
+        loop {
+            let response = match self.stream.next().await {
+                Some(Ok(data)) => data.freeze(),
+                _ => bail!("invalid result")
+            };
+
+            let mut reader = BinReader::from_bytes(&response);
+            let message = ServerMessage::try_from(reader.read_u8()?)?;
+            match read_streamed_message(message, &mut reader)? {
+                Some(streamed) => self.streamed.push_back(streamed),
+                None => return Ok(response)
+            }
+        }
+    }
+}
+
+/// Reads the code following the `Err` flag, callers can downcast the returned error to get it.
+fn server_error(reader: &mut BinReader, message: &str) -> GameError {
+    let code = reader
+        .read_u8()
+        .and_then(ErrorCode::try_from)
+        .unwrap_or(ErrorCode::Unknown);
+    GameError::new(code, message)
+}
+
+/// Parses messages the server pushes without a request, returns `None` for responses.
+fn read_streamed_message(message: ServerMessage, reader: &mut BinReader) -> anyhow::Result<Option<StreamedMessage>> {
+    Ok(match message {
+        ServerMessage::Challenged => Some(StreamedMessage::Challenged),
+        ServerMessage::Attempt => {
+            let correct = reader.read_u8()? > 0;
+            let word = reader.read_str()?;
+            let letters = read_letters(reader)?;
+
+            if letters.is_empty() {
+                Some(StreamedMessage::Attempt(correct, word))
+            } else {
+                Some(StreamedMessage::AttemptWithFeedback(AttemptFeedback { correct, letters }, word))
+            }
+        },
+        ServerMessage::Hint => Some(StreamedMessage::Hint(reader.read_str()?)),
+        ServerMessage::MatchEnded => Some(StreamedMessage::MatchEnded(MatchScore::read(reader)?)),
+        ServerMessage::HangmanState => Some(StreamedMessage::HangmanState(HangmanState::read(reader)?)),
+        ServerMessage::LetterGuessed => Some(StreamedMessage::LetterGuessed(reader.read_str()?, LetterGuess::read(reader)?)),
+        ServerMessage::Chat => Some(StreamedMessage::Chat(ChatMessage::read(reader)?)),
+        ServerMessage::TournamentBracket => {
+            let count = reader.read_u16()? as usize;
+            let mut pairings = Vec::with_capacity(count);
+
+            for _ in 0..count {
+                pairings.push(TournamentPairing::read(reader)?);
+            }
+
+            Some(StreamedMessage::TournamentBracket(pairings))
+        },
+        ServerMessage::TournamentWordRequest => Some(StreamedMessage::TournamentWordRequested(reader.read_u32()?)),
+        ServerMessage::TournamentWinner => {
+            Some(StreamedMessage::TournamentWinner(Some(reader.read_u32()?).filter(|id| *id != SERVER_ID)))
+        },
+        ServerMessage::RaceStandings => {
+            let count = reader.read_u16()? as usize;
+            let mut standings = Vec::with_capacity(count);
+
+            for _ in 0..count {
+                standings.push(RaceStanding::read(reader)?);
+            }
+
+            Some(StreamedMessage::RaceStandings(standings))
+        },
+        _ => None
+    })
+}
@@ -0,0 +1,292 @@
+use anyhow::{bail, Context};
+use serde_json::{Map, Number, Value};
+use crate::bin_reader::BinReader;
+use crate::bin_writer::BinWriter;
+use crate::client::ClientMessage;
+use crate::server::ServerMessage;
+use crate::utils::{ClientId, MAX_PACKET_LENGTH};
+
+/// Longest JSON line accepted, the text form of a frame is a few times larger than the frame.
+pub const MAX_JSON_LINE_LENGTH: usize = MAX_PACKET_LENGTH * 4;
+
+/// Encoding of a single value in a binary frame.
+enum Kind {
+    U8,
+    U16,
+    U32,
+    U64,
+    /// A `u8` that is 0 or 1.
+    Bool,
+    Str,
+    /// `u16` count followed by the items.
+    List(&'static Kind),
+    /// `u8` flag followed by the value only when it is set, `null` in JSON.
+    Optional(&'static Kind),
+    /// `u8` flag followed by the value that is always written, `null` in JSON when the flag isn't set.
+    Flagged(&'static Kind),
+    Object(&'static [Field]),
+}
+
+type Field = (&'static str, Kind);
+
+const HANGMAN_STATE: &[Field] = &[("mask", Kind::Str), ("mistakes", Kind::U8), ("max_mistakes", Kind::U8)];
+
+const LETTERS: Kind = Kind::List(&Kind::U8);
+
+/// Fields following the flag of a client message, in the order they are written.
+fn client_fields(message: &ClientMessage) -> &'static [Field] {
+    match message {
+        ClientMessage::Authorization => &[("password", Kind::Str), ("name", Kind::Str), ("account_password", Kind::Str)],
+        ClientMessage::ListOfOpponents => &[],
+        ClientMessage::RequestMatch => &[("opponent_id", Kind::U32), ("word", Kind::Str), ("mode", Kind::U8)],
+        ClientMessage::SendHint => &[("hint", Kind::Str)],
+        ClientMessage::SendAttempt => &[("word", Kind::Str)],
+        ClientMessage::MatchHistory => &[("limit", Kind::U16)],
+        ClientMessage::Leaderboard => &[("count", Kind::U16)],
+        ClientMessage::GuessLetter => &[("letter", Kind::Str)],
+        ClientMessage::RequestSoloMatch => &[("mode", Kind::U8), ("seed", Kind::Optional(&Kind::U64))],
+        ClientMessage::SendChat => &[("text", Kind::Str)],
+        ClientMessage::SendDirectMessage => &[("recipient_id", Kind::U32), ("text", Kind::Str)],
+        ClientMessage::ListOfRooms => &[],
+        ClientMessage::CreateRoom | ClientMessage::JoinRoom => &[("name", Kind::Str), ("password", Kind::Str)],
+        ClientMessage::RequestRace => &[
+            ("word", Kind::Str),
+            ("mode", Kind::U8),
+            ("opponent_ids", Kind::List(&Kind::U32)),
+        ],
+        ClientMessage::RequestTournament => &[
+            ("format", Kind::U8),
+            ("words", Kind::U8),
+            ("mode", Kind::U8),
+            ("player_ids", Kind::List(&Kind::U32)),
+        ],
+        ClientMessage::SendTournamentWord => &[("word", Kind::Str)],
+        ClientMessage::AdminAuthorization => &[("password", Kind::Str)],
+        ClientMessage::ClearLockout => &[("ip", Kind::Str)],
+        ClientMessage::ListOfConnections | ClientMessage::ListOfMatches => &[],
+        ClientMessage::Kick => &[("id", Kind::U32)],
+        ClientMessage::EndMatch => &[("opponent_id", Kind::U32)],
+        ClientMessage::Broadcast => &[("text", Kind::Str)],
+        ClientMessage::Ban | ClientMessage::Unban => &[("kind", Kind::U8), ("target", Kind::Str)],
+    }
+}
+
+/// Fields following the flag of a server message, in the order they are written.
+fn server_fields(message: ServerMessage) -> &'static [Field] {
+    match message {
+        ServerMessage::Ok | ServerMessage::RequestAuthorization | ServerMessage::MatchBegan | ServerMessage::Challenged => &[],
+        ServerMessage::Err => &[("code", Kind::U8)],
+        ServerMessage::Hint => &[("hint", Kind::Str)],
+        ServerMessage::ListOfOpponents => &[("opponent_ids", Kind::List(&Kind::U32))],
+        ServerMessage::Attempt => &[("correct", Kind::Bool), ("word", Kind::Str), ("letters", LETTERS)],
+        ServerMessage::AttemptResult => &[("correct", Kind::Bool), ("letters", LETTERS)],
+        ServerMessage::MatchHistory => &[("matches", Kind::List(&Kind::Object(&[
+            ("challenger_id", Kind::U32),
+            ("challenger_name", Kind::Str),
+            ("opponent_id", Kind::U32),
+            ("opponent_name", Kind::Str),
+            ("word", Kind::Str),
+            ("started_at", Kind::U64),
+            ("ended_at", Kind::U64),
+            ("outcome", Kind::U8),
+            ("attempts", Kind::U16),
+            ("hints", Kind::U16),
+        ])))],
+        ServerMessage::Leaderboard => &[("entries", Kind::List(&Kind::Object(&[("name", Kind::Str), ("rating", Kind::U32)])))],
+        ServerMessage::MatchEnded => &[("outcome", Kind::U8), ("guesser", Kind::U32), ("challenger", Kind::U32)],
+        ServerMessage::HangmanState => HANGMAN_STATE,
+        ServerMessage::LetterResult => &[("hit", Kind::Bool), ("state", Kind::Object(HANGMAN_STATE))],
+        ServerMessage::LetterGuessed => &[("letter", Kind::Str), ("hit", Kind::Bool), ("state", Kind::Object(HANGMAN_STATE))],
+        ServerMessage::Chat => &[
+            ("sender_id", Kind::U32),
+            ("sender_name", Kind::Str),
+            ("direct", Kind::Bool),
+            ("text", Kind::Str),
+        ],
+        ServerMessage::ListOfRooms => &[("rooms", Kind::List(&Kind::Object(&[
+            ("name", Kind::Str),
+            ("has_password", Kind::Bool),
+            ("players", Kind::U16),
+        ])))],
+        ServerMessage::RaceStandings => &[("standings", Kind::List(&Kind::Object(&[
+            ("guesser_id", Kind::U32),
+            ("guesser_name", Kind::Str),
+            ("outcome", Kind::U8),
+            ("guesses", Kind::U16),
+            ("score", Kind::U32),
+        ])))],
+        ServerMessage::TournamentBracket => &[("pairings", Kind::List(&Kind::Object(&[
+            ("round", Kind::U16),
+            ("first", Kind::U32),
+            ("second", Kind::U32),
+            ("first_score", Kind::Flagged(&Kind::U32)),
+            ("second_score", Kind::Flagged(&Kind::U32)),
+            ("state", Kind::U8),
+            ("winner", Kind::U32),
+        ])))],
+        ServerMessage::TournamentWordRequest => &[("opponent_id", Kind::U32)],
+        ServerMessage::TournamentWinner => &[("winner_id", Kind::U32)],
+        ServerMessage::ListOfConnections => &[("connections", Kind::List(&Kind::Object(&[
+            ("id", Kind::U32),
+            ("ip", Kind::Str),
+            ("name", Kind::Str),
+            ("room", Kind::Str),
+            ("in_match", Kind::Bool),
+            ("admin", Kind::Bool),
+        ])))],
+        ServerMessage::ListOfMatches => &[("matches", Kind::List(&Kind::Object(&[
+            ("room", Kind::Str),
+            ("challenger_id", Kind::U32),
+            ("opponent_id", Kind::U32),
+            ("mode", Kind::U8),
+            ("started_at", Kind::U64),
+        ])))],
+    }
+}
+
+/// Turns a line like `{"type":"SendAttempt","word":"apple"}` into the binary frame of the message.
+pub fn client_message_from_json(line: &str) -> anyhow::Result<Vec<u8>> {
+    let value: Value = serde_json::from_str(line)?;
+    let Some(object) = value.as_object() else {
+        bail!("Expected a JSON object");
+    };
+    let Some(name) = object.get("type").and_then(Value::as_str) else {
+        bail!("Missing message type");
+    };
+    let Some(message) = (0..=u8::MAX)
+        .map_while(|v| ClientMessage::try_from(v).ok())
+        .find(|m| format!("{:?}", m) == name) else {
+        bail!("Unknown message type '{}'", name);
+    };
+
+    let mut bin_writer = BinWriter::with_capacity(MAX_PACKET_LENGTH);
+    bin_writer.write_u8(message.clone() as u8);
+    write_fields(client_fields(&message), object, &mut bin_writer)?;
+
+    Ok(bin_writer.clone_data())
+}
+
+/// Turns a binary frame a client sent into a JSON line without the trailing newline.
+pub fn client_message_to_json(frame: &[u8]) -> anyhow::Result<String> {
+    let mut bin_reader = BinReader::from_slice(frame);
+    let message = ClientMessage::try_from(bin_reader.read_u8()?)?;
+
+    let mut object = Map::new();
+    object.insert("type".to_string(), Value::from(format!("{:?}", message)));
+    read_fields(client_fields(&message), &mut bin_reader, &mut object)?;
+
+    Ok(Value::Object(object).to_string())
+}
+
+/// Turns a binary frame the server sent into a JSON line without the trailing newline.
+pub fn server_message_to_json(frame: &[u8]) -> anyhow::Result<String> {
+    let mut bin_reader = BinReader::from_slice(frame);
+    let message = ServerMessage::try_from(bin_reader.read_u8()?)?;
+
+    let mut object = Map::new();
+    object.insert("type".to_string(), Value::from(format!("{:?}", message)));
+    read_fields(server_fields(message), &mut bin_reader, &mut object)?;
+
+    Ok(Value::Object(object).to_string())
+}
+
+/// The only response without a flag, the id sent back after a successful authorization.
+pub fn authorized_to_json(id: ClientId) -> String {
+    serde_json::json!({ "type": "Authorized", "id": id }).to_string()
+}
+
+fn write_fields(fields: &[Field], object: &Map<String, Value>, bin_writer: &mut BinWriter) -> anyhow::Result<()> {
+    for (name, kind) in fields {
+        let value = object.get(*name).unwrap_or(&Value::Null);
+        write_value(kind, value, bin_writer).with_context(|| format!("Invalid field '{}'", name))?;
+    }
+    Ok(())
+}
+
+fn write_value(kind: &Kind, value: &Value, bin_writer: &mut BinWriter) -> anyhow::Result<()> {
+    let number = |max: u64| match value.as_u64() {
+        Some(n) if n <= max => Ok(n),
+        _ => bail!("Expected a number up to {}", max),
+    };
+
+    match kind {
+        Kind::U8 => bin_writer.write_u8(number(u8::MAX as u64)? as u8),
+        Kind::U16 => bin_writer.write_u16(number(u16::MAX as u64)? as u16),
+        Kind::U32 => bin_writer.write_u32(number(u32::MAX as u64)? as u32),
+        Kind::U64 => bin_writer.write_u64(number(u64::MAX)?),
+        Kind::Bool => match value.as_bool() {
+            Some(b) => bin_writer.write_u8(b as u8),
+            None => bail!("Expected a boolean"),
+        },
+        Kind::Str => match value.as_str() {
+            Some(s) => bin_writer.write_str(s),
+            None => bail!("Expected a string"),
+        },
+        Kind::List(item) => {
+            let Some(items) = value.as_array() else {
+                bail!("Expected an array");
+            };
+            bin_writer.write_u16(items.len() as u16);
+            for item_value in items {
+                write_value(item, item_value, bin_writer)?;
+            }
+        }
+        Kind::Optional(inner) => {
+            bin_writer.write_u8(!value.is_null() as u8);
+            if !value.is_null() {
+                write_value(inner, value, bin_writer)?;
+            }
+        }
+        Kind::Flagged(inner) => {
+            bin_writer.write_u8(!value.is_null() as u8);
+            match value {
+                Value::Null => write_value(inner, &Value::from(0), bin_writer)?,
+                _ => write_value(inner, value, bin_writer)?,
+            }
+        }
+        Kind::Object(fields) => {
+            let Some(object) = value.as_object() else {
+                bail!("Expected an object");
+            };
+            write_fields(fields, object, bin_writer)?;
+        }
+    }
+    Ok(())
+}
+
+fn read_fields(fields: &[Field], bin_reader: &mut BinReader, object: &mut Map<String, Value>) -> anyhow::Result<()> {
+    for (name, kind) in fields {
+        object.insert(name.to_string(), read_value(kind, bin_reader)?);
+    }
+    Ok(())
+}
+
+fn read_value(kind: &Kind, bin_reader: &mut BinReader) -> anyhow::Result<Value> {
+    Ok(match kind {
+        Kind::U8 => Value::from(bin_reader.read_u8()?),
+        Kind::U16 => Value::from(bin_reader.read_u16()?),
+        Kind::U32 => Value::from(bin_reader.read_u32()?),
+        Kind::U64 => Value::Number(Number::from(bin_reader.read_u64()?)),
+        Kind::Bool => Value::from(bin_reader.read_u8()? > 0),
+        Kind::Str => Value::from(bin_reader.read_str()?),
+        Kind::List(item) => {
+            let count = bin_reader.read_u16()? as usize;
+            let items = (0..count).map(|_| read_value(item, bin_reader)).collect::<anyhow::Result<Vec<_>>>()?;
+            Value::Array(items)
+        }
+        Kind::Optional(inner) => match bin_reader.read_u8()? {
+            0 => Value::Null,
+            _ => read_value(inner, bin_reader)?,
+        },
+        Kind::Flagged(inner) => {
+            let set = bin_reader.read_u8()? > 0;
+            let value = read_value(inner, bin_reader)?;
+            if set { value } else { Value::Null }
+        }
+        Kind::Object(fields) => {
+            let mut object = Map::new();
+            read_fields(fields, bin_reader, &mut object)?;
+            Value::Object(object)
+        }
+    })
+}
@@ -1,21 +1,52 @@
 mod server;
 mod client;
+mod bot;
 mod utils;
 mod bin_writer;
 mod bin_reader;
+mod tls;
+mod json;
+mod capture;
 
-pub use server::run_async;
+pub use server::{
+    run_async, run_with_acceptor_async, run_with_config_async, Acceptor, AnswerNormalization, AttemptFeedback, Ban, CharacterSet, ChatMessage, ChatRules,
+    Connection, ConnectionInfo, ErrorCode, GameError, GameRules,
+    HangmanRules, HangmanState, HintRules, LeakCheck, LeaderboardEntry, LetterFeedback, LetterGuess, LockoutRules, MatchInfo, MatchMode,
+    MatchOutcome, MatchScore, MatchSummary, MemoryAcceptor, MemoryConnector, NormalizationForm, PairingState, RaceStanding, RateLimit, RateLimits, RoomConfig, RoomInfo, ScoringConfig,
+    ServerConfig, SoloRules, TcpAcceptor, TournamentFormat, TournamentPairing, TournamentRules, Transport, WordRules, WordSource, HIDDEN_LETTER, LOBBY_ROOM, MAX_TOURNAMENT_PAIRINGS, SERVER_ID
+};
+#[cfg(unix)]
+pub use server::UnixAcceptor;
 pub use client::{Client, StreamedMessage};
-pub use utils::ClientId;
+pub use bot::{
+    run_bot_async, Bot, BotConfig, BotRole, Challenge, ChallengerBot, FilteringStrategy, GuessStrategy, GuesserBot,
+    SequentialStrategy
+};
+pub use capture::{
+    describe_capture, parse_capture, read_capture, replay_capture_async, CaptureKind, CaptureRecord, ReplayMismatch, ReplayPasswords, ReplayReport
+};
+pub use json::{client_message_from_json, client_message_to_json, server_message_to_json};
+pub use tls::{ClientTlsConfig, ServerTlsConfig, ServerTrust};
+pub use utils::{AsyncStream, BoxedStream, ClientId};
 
 #[cfg(test)]
 mod tests {
+    use crate::bot::{run_bot_async, BotConfig, ChallengerBot, FilteringStrategy, GuesserBot};
     use crate::client::{Client, StreamedMessage};
-    use crate::server::run_async;
+    use crate::capture::{describe_capture, read_capture, replay_capture_async, CaptureKind, ReplayMismatch, ReplayPasswords};
+    use crate::tls::{ClientTlsConfig, ServerTlsConfig, ServerTrust};
+    use crate::server::{
+        run_async, run_with_acceptor_async, run_with_config_async, AttemptFeedback, Ban, ChatMessage, ErrorCode, GameError, HangmanState, LetterFeedback, LetterGuess,
+        LockoutRules, MatchMode, MatchOutcome, MatchScore, MemoryAcceptor, PairingState, RaceStanding, RateLimit, RateLimits, RoomConfig, RoomInfo, ServerConfig,
+        TournamentFormat, TournamentRules,
+        WordSource, LOBBY_ROOM, SERVER_ID
+    };
 
-    const PASSWORD: &'static str = "password";
+    const PASSWORD: &str = "password";
+    const ACCOUNT_PASSWORD: &str = "secret";
 
     #[tokio::test]
+    #[allow(clippy::bool_assert_comparison)]
     async fn it_works_async() {
 
         _ = tokio::spawn(async move {
@@ -56,7 +87,7 @@ mod tests {
             challenger.read_streamed_message_async().await.unwrap());
 
         // challenger sends a hint
-        let hint = "maybe try 'test'";
+        let hint = "starts with a 't'";
         challenger.send_hint_async(hint).await.unwrap();
 
         // expect hint on opponents side
@@ -72,4 +103,1248 @@ mod tests {
             Some(StreamedMessage::Attempt(true, word.to_string())),
             challenger.read_streamed_message_async().await.unwrap());
     }
+
+    #[tokio::test]
+    async fn match_history() {
+        let history_path = std::env::temp_dir().join(format!("guess_history_{}.bin", std::process::id()));
+        _ = std::fs::remove_file(&history_path);
+
+        let mut config = ServerConfig::new(PASSWORD);
+        config.history_path = Some(history_path.clone());
+        _ = tokio::spawn(async move {
+            _ = run_with_config_async("127.0.0.1:8081", config).await;
+        });
+
+        //wait for the server to start
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let mut challenger = Client::connect_async("127.0.0.1:8081".parse().unwrap(), PASSWORD).await.unwrap();
+        let mut opponent = Client::connect_async("127.0.0.1:8081".parse().unwrap(), PASSWORD).await.unwrap();
+
+        // play two matches, the players should be available again after the first one is solved
+        for word in ["first", "second"] {
+            challenger.request_match_async(opponent.id, word).await.unwrap();
+            assert_eq!(Some(StreamedMessage::Challenged), opponent.read_streamed_message_async().await.unwrap());
+
+            assert!(!opponent.send_attempt_async("wrong").await.unwrap());
+            challenger.read_streamed_message_async().await.unwrap();
+
+            challenger.send_hint_async("a hint").await.unwrap();
+            opponent.read_streamed_message_async().await.unwrap();
+
+            assert!(opponent.send_attempt_async(word).await.unwrap());
+            challenger.read_streamed_message_async().await.unwrap();
+
+            for client in [&mut challenger, &mut opponent] {
+                let ended = client.read_streamed_message_async().await.unwrap();
+                assert!(matches!(ended, Some(StreamedMessage::MatchEnded(_))));
+            }
+        }
+
+        let history = opponent.get_match_history_async(10).await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].word, "second");
+        assert_eq!(history[0].challenger_id, challenger.id);
+        assert_eq!(history[0].opponent_id, opponent.id);
+        assert_eq!(history[0].outcome, MatchOutcome::Solved);
+        assert_eq!(history[0].attempts, 2);
+        assert_eq!(history[0].hints, 1);
+        assert_eq!(history[1].word, "first");
+
+        assert_eq!(challenger.get_match_history_async(1).await.unwrap().len(), 1);
+
+        // a corrupt record is skipped, and client ids restart so unnamed players don't get the old matches
+        let mut data = std::fs::read(&history_path).unwrap();
+        data.extend_from_slice(&[3, 0, 0, 0, 0xff, 0xff, 0xff]);
+        std::fs::write(&history_path, data).unwrap();
+
+        let mut config = ServerConfig::new(PASSWORD);
+        config.history_path = Some(history_path.clone());
+        _ = tokio::spawn(async move {
+            _ = run_with_config_async("127.0.0.1:8108", config).await;
+        });
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let mut stranger = Client::connect_async("127.0.0.1:8108".parse().unwrap(), PASSWORD).await.unwrap();
+        assert_eq!(challenger.id, stranger.id);
+        assert!(stranger.get_match_history_async(10).await.unwrap().is_empty());
+
+        _ = std::fs::remove_file(&history_path);
+    }
+
+    #[tokio::test]
+    async fn ratings_and_leaderboard() {
+        let ratings_path = std::env::temp_dir().join(format!("guess_ratings_{}.bin", std::process::id()));
+        let accounts_path = std::env::temp_dir().join(format!("guess_accounts_{}.bin", std::process::id()));
+        _ = std::fs::remove_file(&ratings_path);
+        _ = std::fs::remove_file(&accounts_path);
+
+        let mut config = ServerConfig::new(PASSWORD);
+        config.ratings_path = Some(ratings_path.clone());
+        config.accounts_path = Some(accounts_path.clone());
+        _ = tokio::spawn(async move {
+            _ = run_with_config_async("127.0.0.1:8082", config).await;
+        });
+
+        //wait for the server to start
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let addr = "127.0.0.1:8082".parse().unwrap();
+        let mut challenger = Client::connect_as_async(addr, PASSWORD, "alice", ACCOUNT_PASSWORD).await.unwrap();
+        let mut opponent = Client::connect_as_async(addr, PASSWORD, "bob", ACCOUNT_PASSWORD).await.unwrap();
+
+        // names are unique among connected players
+        assert!(Client::connect_as_async(addr, PASSWORD, "bob", ACCOUNT_PASSWORD).await.is_err());
+
+        challenger.request_match_async(opponent.id, "word").await.unwrap();
+        opponent.read_streamed_message_async().await.unwrap();
+        assert!(opponent.send_attempt_async("word").await.unwrap());
+        challenger.read_streamed_message_async().await.unwrap();
+
+        let leaderboard = challenger.get_leaderboard_async(10).await.unwrap();
+        assert_eq!(leaderboard.len(), 2);
+        assert_eq!(leaderboard[0].name, "bob");
+        assert!(leaderboard[0].rating > 1200);
+        assert_eq!(leaderboard[1].name, "alice");
+        assert!(leaderboard[1].rating < 1200);
+
+        // ratings are loaded again by a new server
+        let mut config = ServerConfig::new(PASSWORD);
+        config.ratings_path = Some(ratings_path.clone());
+        config.accounts_path = Some(accounts_path.clone());
+        _ = tokio::spawn(async move {
+            _ = run_with_config_async("127.0.0.1:8083", config).await;
+        });
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let mut client = Client::connect_async("127.0.0.1:8083".parse().unwrap(), PASSWORD).await.unwrap();
+        assert_eq!(client.get_leaderboard_async(1).await.unwrap(), leaderboard[..1]);
+
+        // so are the accounts, nobody else can play as bob
+        let addr = "127.0.0.1:8083".parse().unwrap();
+        assert!(Client::connect_as_async(addr, PASSWORD, "bob", "guess").await.is_err());
+        assert!(Client::connect_as_async(addr, PASSWORD, "dave", "").await.is_err());
+        Client::connect_as_async(addr, PASSWORD, "bob", ACCOUNT_PASSWORD).await.unwrap();
+
+        _ = std::fs::remove_file(&ratings_path);
+        _ = std::fs::remove_file(&accounts_path);
+    }
+
+    #[tokio::test]
+    async fn match_score() {
+        let mut config = ServerConfig::new(PASSWORD);
+        config.rules.scoring.second_penalty = 0;
+        _ = tokio::spawn(async move {
+            _ = run_with_config_async("127.0.0.1:8084", config).await;
+        });
+
+        //wait for the server to start
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let mut challenger = Client::connect_async("127.0.0.1:8084".parse().unwrap(), PASSWORD).await.unwrap();
+        let mut opponent = Client::connect_async("127.0.0.1:8084".parse().unwrap(), PASSWORD).await.unwrap();
+
+        challenger.request_match_async(opponent.id, "test").await.unwrap();
+        opponent.read_streamed_message_async().await.unwrap();
+
+        assert!(!opponent.send_attempt_async("wrong").await.unwrap());
+        challenger.send_hint_async("starts with t").await.unwrap();
+
+        // the hint has to arrive before the word is solved
+        assert_eq!(Some(StreamedMessage::Hint("starts with t".to_string())), opponent.read_streamed_message_async().await.unwrap());
+        assert!(opponent.send_attempt_async("test").await.unwrap());
+
+        // one extra attempt and a hint cost the guesser, the challenger gets 3 distinct letters and a failed attempt
+        let expected = StreamedMessage::MatchEnded(MatchScore {
+            outcome: MatchOutcome::Solved,
+            guesser: 1000 - 100 - 150,
+            challenger: 3 * 20 + 50,
+        });
+
+        assert_eq!(Some(expected.clone()), opponent.read_streamed_message_async().await.unwrap());
+
+        challenger.read_streamed_message_async().await.unwrap();
+        challenger.read_streamed_message_async().await.unwrap();
+        assert_eq!(Some(expected), challenger.read_streamed_message_async().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn hint_limits() {
+        let mut config = ServerConfig::new(PASSWORD);
+        config.rules.hints.max_hints = 2;
+        config.rules.hints.min_interval = std::time::Duration::from_millis(200);
+        config.rules.hints.max_length = 10;
+        _ = tokio::spawn(async move {
+            _ = run_with_config_async("127.0.0.1:8085", config).await;
+        });
+
+        //wait for the server to start
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let mut challenger = Client::connect_async("127.0.0.1:8085".parse().unwrap(), PASSWORD).await.unwrap();
+        let mut opponent = Client::connect_async("127.0.0.1:8085".parse().unwrap(), PASSWORD).await.unwrap();
+
+        challenger.request_match_async(opponent.id, "word").await.unwrap();
+
+        let error_code = |result: anyhow::Result<()>| result.unwrap_err().downcast_ref::<GameError>().unwrap().code;
+
+        challenger.send_hint_async("first").await.unwrap();
+        assert_eq!(ErrorCode::HintTooSoon, error_code(challenger.send_hint_async("second").await));
+        assert_eq!(ErrorCode::HintTooLong, error_code(challenger.send_hint_async("this is too long").await));
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+        challenger.send_hint_async("second").await.unwrap();
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+        assert_eq!(ErrorCode::HintLimitReached, error_code(challenger.send_hint_async("third").await));
+
+        // only the accepted hints reach the opponent
+        assert_eq!(Some(StreamedMessage::Challenged), opponent.read_streamed_message_async().await.unwrap());
+        assert_eq!(Some(StreamedMessage::Hint("first".to_string())), opponent.read_streamed_message_async().await.unwrap());
+        assert_eq!(Some(StreamedMessage::Hint("second".to_string())), opponent.read_streamed_message_async().await.unwrap());
+        assert_eq!(None, opponent.read_streamed_message_async().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn hint_leaks() {
+        let mut config = ServerConfig::new(PASSWORD);
+        config.rules.hints.min_interval = std::time::Duration::ZERO;
+        config.rules.hints.max_hints = 10;
+        _ = tokio::spawn(async move {
+            _ = run_with_config_async("127.0.0.1:8086", config).await;
+        });
+
+        //wait for the server to start
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let mut challenger = Client::connect_async("127.0.0.1:8086".parse().unwrap(), PASSWORD).await.unwrap();
+        let opponent = Client::connect_async("127.0.0.1:8086".parse().unwrap(), PASSWORD).await.unwrap();
+
+        challenger.request_match_async(opponent.id, "Café").await.unwrap();
+
+        for hint in ["maybe try 'CAFÉ'", "maybe try 'cafe\u{301}'", "c a f é", "c-a-f-é", "caffé"] {
+            let err = challenger.send_hint_async(hint).await.unwrap_err();
+            assert_eq!(ErrorCode::HintLeaksWord, err.downcast_ref::<GameError>().unwrap().code, "{}", hint);
+        }
+
+        challenger.send_hint_async("you drink coffee there").await.unwrap();
+
+        // hints are checked with the same case folding as answers
+        let mut challenger = Client::connect_async("127.0.0.1:8086".parse().unwrap(), PASSWORD).await.unwrap();
+        let opponent = Client::connect_async("127.0.0.1:8086".parse().unwrap(), PASSWORD).await.unwrap();
+
+        challenger.request_match_async(opponent.id, "Straße").await.unwrap();
+
+        let err = challenger.send_hint_async("strasse").await.unwrap_err();
+        assert_eq!(ErrorCode::HintLeaksWord, err.downcast_ref::<GameError>().unwrap().code);
+    }
+
+    #[tokio::test]
+    async fn challenge_word_validation() {
+        let words_path = std::env::temp_dir().join(format!("guess_words_{}.txt", std::process::id()));
+        std::fs::write(&words_path, "apple\n# fruits\nBanana\nStraße\n").unwrap();
+
+        let mut config = ServerConfig::new(PASSWORD);
+        config.rules.words.word_lists.push(words_path.clone());
+        config.rules.words.min_length = 3;
+        config.rules.words.max_length = 10;
+        _ = tokio::spawn(async move {
+            _ = run_with_config_async("127.0.0.1:8087", config).await;
+        });
+
+        //wait for the server to start
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let mut challenger = Client::connect_async("127.0.0.1:8087".parse().unwrap(), PASSWORD).await.unwrap();
+        let opponent = Client::connect_async("127.0.0.1:8087".parse().unwrap(), PASSWORD).await.unwrap();
+
+        let rejected = [
+            ("", ErrorCode::WordTooShort),
+            ("ab", ErrorCode::WordTooShort),
+            ("bananabananabanana", ErrorCode::WordTooLong),
+            ("   ", ErrorCode::WordInvalidCharacters),
+            ("b4nana", ErrorCode::WordInvalidCharacters),
+            ("pear", ErrorCode::WordNotInDictionary),
+        ];
+        for (word, code) in rejected {
+            let err = challenger.request_match_async(opponent.id, word).await.unwrap_err();
+            assert_eq!(code, err.downcast_ref::<GameError>().unwrap().code, "{}", word);
+        }
+
+        assert!(challenger.request_match_async(challenger.id, "apple").await.is_err());
+
+        // failed requests don't leave the players in a match, listed words are compared like answers
+        challenger.request_match_async(opponent.id, "STRASSE").await.unwrap();
+
+        _ = std::fs::remove_file(&words_path);
+    }
+
+    #[tokio::test]
+    async fn answer_normalization() {
+        let mut config = ServerConfig::new(PASSWORD);
+        config.rules.answers.strip_diacritics = true;
+        config.rules.answers.trim_punctuation = true;
+        _ = tokio::spawn(async move {
+            _ = run_with_config_async("127.0.0.1:8088", config).await;
+        });
+
+        //wait for the server to start
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let mut challenger = Client::connect_async("127.0.0.1:8088".parse().unwrap(), PASSWORD).await.unwrap();
+        let mut opponent = Client::connect_async("127.0.0.1:8088".parse().unwrap(), PASSWORD).await.unwrap();
+
+        let cases = [
+            ("Straße", "STRASSE"),
+            ("café", "CAFÉ"),
+            ("café", " Cafe! "),
+            ("naïve", "nai\u{308}ve"),
+        ];
+        for (word, attempt) in cases {
+            challenger.request_match_async(opponent.id, word).await.unwrap();
+            assert!(!opponent.send_attempt_async("wrong").await.unwrap());
+            assert!(opponent.send_attempt_async(attempt).await.unwrap(), "{} {}", word, attempt);
+        }
+    }
+
+    #[tokio::test]
+    async fn wordle_feedback() {
+        _ = tokio::spawn(async move {
+            _ = run_async("127.0.0.1:8089", PASSWORD).await;
+        });
+
+        //wait for the server to start
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let mut challenger = Client::connect_async("127.0.0.1:8089".parse().unwrap(), PASSWORD).await.unwrap();
+        let mut opponent = Client::connect_async("127.0.0.1:8089".parse().unwrap(), PASSWORD).await.unwrap();
+
+        challenger.request_match_with_mode_async(opponent.id, "Crane", MatchMode::Wordle).await.unwrap();
+
+        // attempts have to be as long as the word
+        let err = opponent.send_attempt_with_feedback_async("car").await.unwrap_err();
+        assert_eq!(ErrorCode::AttemptWrongLength, err.downcast_ref::<GameError>().unwrap().code);
+
+        use LetterFeedback::*;
+        let feedback = AttemptFeedback {
+            correct: false,
+            letters: vec![Correct, Present, Absent, Absent, Present],
+        };
+        assert_eq!(feedback, opponent.send_attempt_with_feedback_async("caddr").await.unwrap());
+
+        // the challenger sees the same feedback
+        assert_eq!(
+            Some(StreamedMessage::AttemptWithFeedback(feedback, "caddr".to_string())),
+            challenger.read_streamed_message_async().await.unwrap());
+
+        let feedback = opponent.send_attempt_with_feedback_async("CRANE").await.unwrap();
+        assert!(feedback.correct);
+        assert_eq!(feedback.letters, vec![Correct; 5]);
+    }
+
+    #[tokio::test]
+    async fn hangman() {
+        let mut config = ServerConfig::new(PASSWORD);
+        config.rules.hangman.max_mistakes = 2;
+        config.rules.scoring.second_penalty = 0;
+        _ = tokio::spawn(async move {
+            _ = run_with_config_async("127.0.0.1:8090", config).await;
+        });
+
+        //wait for the server to start
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let mut challenger = Client::connect_async("127.0.0.1:8090".parse().unwrap(), PASSWORD).await.unwrap();
+        let mut opponent = Client::connect_async("127.0.0.1:8090".parse().unwrap(), PASSWORD).await.unwrap();
+
+        let state = |mask: &str, mistakes| HangmanState { mask: mask.to_string(), mistakes, max_mistakes: 2 };
+
+        challenger.request_match_with_mode_async(opponent.id, "Apple", MatchMode::Hangman).await.unwrap();
+        assert_eq!(Some(StreamedMessage::Challenged), opponent.read_streamed_message_async().await.unwrap());
+        assert_eq!(Some(StreamedMessage::HangmanState(state("_____", 0))), opponent.read_streamed_message_async().await.unwrap());
+
+        // the mask keeps the word as it was written
+        let guess = LetterGuess { hit: true, state: state("_pp__", 0) };
+        assert_eq!(guess, opponent.guess_letter_async('P').await.unwrap());
+        assert_eq!(
+            Some(StreamedMessage::LetterGuessed("P".to_string(), guess)),
+            challenger.read_streamed_message_async().await.unwrap());
+
+        let err = opponent.guess_letter_async('p').await.unwrap_err();
+        assert_eq!(ErrorCode::LetterAlreadyGuessed, err.downcast_ref::<GameError>().unwrap().code);
+
+        // the second mistake loses the match
+        assert_eq!(LetterGuess { hit: false, state: state("_pp__", 1) }, opponent.guess_letter_async('x').await.unwrap());
+        assert_eq!(LetterGuess { hit: false, state: state("_pp__", 2) }, opponent.guess_letter_async('y').await.unwrap());
+        assert_eq!(
+            Some(StreamedMessage::MatchEnded(MatchScore { outcome: MatchOutcome::Failed, guesser: 0, challenger: 0 })),
+            opponent.read_streamed_message_async().await.unwrap());
+
+        // revealing every letter solves the match, the wrong letter is scored like a wrong attempt
+        challenger.request_match_with_mode_async(opponent.id, "Apple", MatchMode::Hangman).await.unwrap();
+        for letter in ['a', 'p', 'l'] {
+            assert!(opponent.guess_letter_async(letter).await.unwrap().hit);
+        }
+        assert!(!opponent.guess_letter_async('z').await.unwrap().hit);
+        assert_eq!(state("Apple", 1), opponent.guess_letter_async('e').await.unwrap().state);
+
+        let ended = loop {
+            match opponent.read_streamed_message_async().await.unwrap() {
+                Some(StreamedMessage::MatchEnded(score)) => break score,
+                Some(_) => continue,
+                None => panic!("match didn't end")
+            }
+        };
+        assert_eq!(MatchScore { outcome: MatchOutcome::Solved, guesser: 900, challenger: 130 }, ended);
+
+        // letters are compared through the answer normalization, "ß" folds to "ss"
+        challenger.request_match_with_mode_async(opponent.id, "Straße", MatchMode::Hangman).await.unwrap();
+        assert_eq!(state("S___ß_", 0), opponent.guess_letter_async('s').await.unwrap().state);
+    }
+
+    #[tokio::test]
+    async fn solo_match() {
+        let words_path = std::env::temp_dir().join(format!("guess_solo_words_{}.txt", std::process::id()));
+        std::fs::write(&words_path, "apple\nbanana\ncherry\n").unwrap();
+
+        let mut config = ServerConfig::new(PASSWORD);
+        config.rules.solo.word_lists.push(words_path.clone());
+        config.rules.solo.hint_after = 1;
+        _ = tokio::spawn(async move {
+            _ = run_with_config_async("127.0.0.1:8091", config).await;
+        });
+
+        //wait for the server to start
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let mut player = Client::connect_async("127.0.0.1:8091".parse().unwrap(), PASSWORD).await.unwrap();
+
+        let mut words = Vec::new();
+        for _ in 0..2 {
+            player.request_solo_match_async(MatchMode::Classic, Some(7)).await.unwrap();
+
+            // every failed attempt reveals another letter
+            assert!(!player.send_attempt_async("wrong").await.unwrap());
+            let Some(StreamedMessage::Hint(hint)) = player.read_streamed_message_async().await.unwrap() else {
+                panic!("expected a hint");
+            };
+
+            let word = ["apple", "banana", "cherry"].into_iter().find(|w| w[..1] == hint[..1]).unwrap();
+            assert_eq!(hint, format!("{}{}", &word[..1], "_".repeat(word.len() - 1)));
+            assert!(player.send_attempt_async(word).await.unwrap());
+
+            let Some(StreamedMessage::MatchEnded(score)) = player.read_streamed_message_async().await.unwrap() else {
+                panic!("expected the match to end");
+            };
+            assert_eq!(MatchOutcome::Solved, score.outcome);
+            words.push(word);
+        }
+
+        // the same seed picks the same word
+        assert_eq!(words[0], words[1]);
+
+        _ = std::fs::remove_file(&words_path);
+    }
+
+    #[tokio::test]
+    async fn bots() {
+        _ = tokio::spawn(async move {
+            _ = run_async("127.0.0.1:8092", PASSWORD).await;
+        });
+
+        //wait for the server to start
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let words = |list: &[&str]| list.iter().map(|w| w.to_string()).collect::<Vec<String>>();
+
+        let mut config = BotConfig::new("127.0.0.1:8092".parse().unwrap(), PASSWORD);
+        config.max_matches = Some(2);
+        let challenger = tokio::spawn(run_bot_async(
+            config,
+            ChallengerBot::new(words(&["grape", "melon"]), MatchMode::Wordle)));
+
+        let mut config = BotConfig::new("127.0.0.1:8092".parse().unwrap(), PASSWORD);
+        config.max_matches = Some(2);
+        let guesser = tokio::spawn(run_bot_async(
+            config,
+            GuesserBot::new(FilteringStrategy::new(words(&["apple", "lemon", "melon", "grape", "mango"])))));
+
+        let timeout = tokio::time::Duration::from_secs(5);
+        let guesser = tokio::time::timeout(timeout, guesser).await.unwrap().unwrap().unwrap();
+        tokio::time::timeout(timeout, challenger).await.unwrap().unwrap().unwrap();
+
+        assert_eq!(guesser.solved, 2);
+        assert_eq!(guesser.failed, 0);
+    }
+
+    #[tokio::test]
+    async fn lobby_chat() {
+        let mut config = ServerConfig::new(PASSWORD);
+        config.rules.chat.max_length = 20;
+        config.rules.chat.max_messages = 2;
+        _ = tokio::spawn(async move {
+            _ = run_with_config_async("127.0.0.1:8093", config).await;
+        });
+
+        //wait for the server to start
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let addr = "127.0.0.1:8093".parse().unwrap();
+        let mut alice = Client::connect_as_async(addr, PASSWORD, "alice", ACCOUNT_PASSWORD).await.unwrap();
+        let mut bob = Client::connect_as_async(addr, PASSWORD, "bob", ACCOUNT_PASSWORD).await.unwrap();
+        let mut carol = Client::connect_as_async(addr, PASSWORD, "carol", ACCOUNT_PASSWORD).await.unwrap();
+
+        let error_code = |result: anyhow::Result<()>| result.unwrap_err().downcast_ref::<GameError>().unwrap().code;
+
+        alice.send_chat_async("hi everyone").await.unwrap();
+        alice.send_direct_message_async(bob.id, "hi bob").await.unwrap();
+
+        let lobby = ChatMessage { sender_id: alice.id, sender_name: "alice".to_string(), direct: false, text: "hi everyone".to_string() };
+        let direct = ChatMessage { direct: true, text: "hi bob".to_string(), ..lobby.clone() };
+        assert_eq!(Some(StreamedMessage::Chat(lobby.clone())), bob.read_streamed_message_async().await.unwrap());
+        assert_eq!(Some(StreamedMessage::Chat(direct)), bob.read_streamed_message_async().await.unwrap());
+        assert_eq!(Some(StreamedMessage::Chat(lobby)), carol.read_streamed_message_async().await.unwrap());
+        assert_eq!(None, carol.read_streamed_message_async().await.unwrap());
+        assert_eq!(None, alice.read_streamed_message_async().await.unwrap());
+
+        // limits
+        assert_eq!(ErrorCode::ChatRateLimited, error_code(alice.send_chat_async("again").await));
+        assert_eq!(ErrorCode::ChatTooLong, error_code(bob.send_chat_async("this message is way too long").await));
+        assert_eq!(ErrorCode::ChatEmpty, error_code(bob.send_chat_async(" ").await));
+        assert_eq!(ErrorCode::RecipientNotFound, error_code(bob.send_direct_message_async(1000, "hello").await));
+    }
+
+    #[tokio::test]
+    async fn rooms() {
+        let mut config = ServerConfig::new(PASSWORD);
+        let mut rules = config.rules.clone();
+        rules.words.min_length = 4;
+        config.rooms.push(RoomConfig { name: "long words".to_string(), password: Some("secret".to_string()), rules });
+        _ = tokio::spawn(async move {
+            _ = run_with_config_async("127.0.0.1:8094", config).await;
+        });
+
+        //wait for the server to start
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let addr = "127.0.0.1:8094".parse().unwrap();
+        let mut alice = Client::connect_as_async(addr, PASSWORD, "alice", ACCOUNT_PASSWORD).await.unwrap();
+        let mut bob = Client::connect_as_async(addr, PASSWORD, "bob", ACCOUNT_PASSWORD).await.unwrap();
+        let mut carol = Client::connect_as_async(addr, PASSWORD, "carol", ACCOUNT_PASSWORD).await.unwrap();
+
+        let error_code = |result: anyhow::Result<()>| result.unwrap_err().downcast_ref::<GameError>().unwrap().code;
+        let room = |name: &str, has_password, players| RoomInfo { name: name.to_string(), has_password, players };
+
+        // everyone starts in the lobby
+        assert_eq!(vec![room(LOBBY_ROOM, false, 3), room("long words", true, 0)], alice.get_rooms_async().await.unwrap());
+
+        assert_eq!(ErrorCode::RoomPasswordWrong, error_code(alice.join_room_async("long words", "guess").await));
+        assert_eq!(ErrorCode::RoomNotFound, error_code(alice.join_room_async("missing", "").await));
+        alice.join_room_async("long words", "secret").await.unwrap();
+        bob.join_room_async("long words", "secret").await.unwrap();
+
+        // opponents and chat are scoped to the room
+        assert_eq!(vec![bob.id], alice.get_opponents_async().await.unwrap());
+        assert!(carol.get_opponents_async().await.unwrap().is_empty());
+        alice.send_chat_async("hi").await.unwrap();
+        assert!(matches!(bob.read_streamed_message_async().await.unwrap(), Some(StreamedMessage::Chat(_))));
+        assert_eq!(None, carol.read_streamed_message_async().await.unwrap());
+        assert!(carol.request_match_async(bob.id, "test").await.is_err());
+
+        // the room's rules apply to its matches
+        assert_eq!(ErrorCode::WordTooShort, error_code(alice.request_match_async(bob.id, "cat").await));
+        alice.request_match_async(bob.id, "test").await.unwrap();
+        assert_eq!(Some(StreamedMessage::Challenged), bob.read_streamed_message_async().await.unwrap());
+        assert_eq!(ErrorCode::PlayerInMatch, error_code(bob.join_room_async(LOBBY_ROOM, "").await));
+
+        // rooms created by players close once they are empty
+        assert_eq!(ErrorCode::RoomExists, error_code(carol.create_room_async("long words", "").await));
+        assert_eq!(ErrorCode::RoomNameInvalid, error_code(carol.create_room_async(" ", "").await));
+        carol.create_room_async("carol's room", "").await.unwrap();
+        assert_eq!(room("carol's room", false, 1), carol.get_rooms_async().await.unwrap()[0]);
+        carol.join_room_async(LOBBY_ROOM, "").await.unwrap();
+        assert_eq!(
+            vec![room(LOBBY_ROOM, false, 1), room("long words", true, 2)],
+            carol.get_rooms_async().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn race() {
+        let mut config = ServerConfig::new(PASSWORD);
+        config.rules.scoring.second_penalty = 0;
+        _ = tokio::spawn(async move {
+            _ = run_with_config_async("127.0.0.1:8095", config).await;
+        });
+
+        //wait for the server to start
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let addr = "127.0.0.1:8095".parse().unwrap();
+        let mut alice = Client::connect_as_async(addr, PASSWORD, "alice", ACCOUNT_PASSWORD).await.unwrap();
+        let mut bob = Client::connect_as_async(addr, PASSWORD, "bob", ACCOUNT_PASSWORD).await.unwrap();
+        let mut carol = Client::connect_as_async(addr, PASSWORD, "carol", ACCOUNT_PASSWORD).await.unwrap();
+
+        assert!(alice.request_race_async(&[bob.id, bob.id], "test", MatchMode::Classic).await.is_err());
+        alice.request_race_async(&[bob.id, carol.id], "test", MatchMode::Classic).await.unwrap();
+        assert_eq!(Some(StreamedMessage::Challenged), bob.read_streamed_message_async().await.unwrap());
+        assert_eq!(Some(StreamedMessage::Challenged), carol.read_streamed_message_async().await.unwrap());
+
+        // hints go to every guesser
+        alice.send_hint_async("a word").await.unwrap();
+        assert_eq!(Some(StreamedMessage::Hint("a word".to_string())), bob.read_streamed_message_async().await.unwrap());
+        assert_eq!(Some(StreamedMessage::Hint("a word".to_string())), carol.read_streamed_message_async().await.unwrap());
+
+        // attempts are only seen by the challenger
+        assert!(!bob.send_attempt_async("text").await.unwrap());
+        assert_eq!(Some(StreamedMessage::Attempt(false, "text".to_string())), alice.read_streamed_message_async().await.unwrap());
+        assert_eq!(None, carol.read_streamed_message_async().await.unwrap());
+
+        // the first correct guess ends the race for everyone
+        assert!(carol.send_attempt_async("test").await.unwrap());
+        assert_eq!(Some(StreamedMessage::Attempt(true, "test".to_string())), alice.read_streamed_message_async().await.unwrap());
+
+        let standings = vec![
+            RaceStanding { guesser_id: carol.id, guesser_name: "carol".to_string(), outcome: MatchOutcome::Solved, guesses: 1, score: 850 },
+            RaceStanding { guesser_id: bob.id, guesser_name: "bob".to_string(), outcome: MatchOutcome::Outraced, guesses: 1, score: 0 },
+        ];
+        async fn expect_end(client: &mut Client, outcome: MatchOutcome, standings: &[RaceStanding]) {
+            let Some(StreamedMessage::MatchEnded(score)) = client.read_streamed_message_async().await.unwrap() else {
+                panic!("expected the match to end");
+            };
+            assert_eq!(outcome, score.outcome);
+            assert_eq!(Some(StreamedMessage::RaceStandings(standings.to_vec())), client.read_streamed_message_async().await.unwrap());
+        }
+        expect_end(&mut bob, MatchOutcome::Outraced, &standings).await;
+        expect_end(&mut carol, MatchOutcome::Solved, &standings).await;
+
+        // the challenger is told about both matches
+        let Some(StreamedMessage::MatchEnded(_)) = alice.read_streamed_message_async().await.unwrap() else { panic!() };
+        expect_end(&mut alice, MatchOutcome::Outraced, &standings).await;
+
+        // everyone is free again
+        assert_eq!(2, alice.get_opponents_async().await.unwrap().len());
+    }
+
+    #[tokio::test]
+    async fn tournament() {
+        let mut config = ServerConfig::new(PASSWORD);
+        config.rules.scoring.second_penalty = 0;
+        _ = tokio::spawn(async move {
+            _ = run_with_config_async("127.0.0.1:8096", config).await;
+        });
+
+        //wait for the server to start
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let addr = "127.0.0.1:8096".parse().unwrap();
+        let mut alice = Client::connect_as_async(addr, PASSWORD, "alice", ACCOUNT_PASSWORD).await.unwrap();
+        let mut bob = Client::connect_as_async(addr, PASSWORD, "bob", ACCOUNT_PASSWORD).await.unwrap();
+        let mut carol = Client::connect_as_async(addr, PASSWORD, "carol", ACCOUNT_PASSWORD).await.unwrap();
+
+        // skips pushes until the expected one arrives
+        async fn expect(client: &mut Client, expected: StreamedMessage) {
+            loop {
+                match client.read_streamed_message_async().await.unwrap() {
+                    Some(message) if message == expected => return,
+                    Some(_) => continue,
+                    None => panic!("expected {:?}", expected),
+                }
+            }
+        }
+
+        // the guesser with fewer attempts wins a pairing
+        async fn play(challenger: &mut Client, guesser: &mut Client, word: &str, wrong: &str) {
+            expect(challenger, StreamedMessage::TournamentWordRequested(guesser.id)).await;
+            challenger.send_tournament_word_async(word).await.unwrap();
+            expect(guesser, StreamedMessage::Challenged).await;
+            if !wrong.is_empty() {
+                assert!(!guesser.send_attempt_async(wrong).await.unwrap());
+            }
+            assert!(guesser.send_attempt_async(word).await.unwrap());
+        }
+
+        let error_code = |result: anyhow::Result<()>| result.unwrap_err().downcast_ref::<GameError>().unwrap().code;
+
+        // a round robin of 9 players has 36 pairings, too many for a single bracket frame
+        let mut crowd = vec![alice.id, bob.id, carol.id];
+        let mut others = Vec::new();
+        for _ in 0..6 {
+            let other = Client::connect_async(addr, PASSWORD).await.unwrap();
+            crowd.push(other.id);
+            others.push(other);
+        }
+        assert_eq!(
+            ErrorCode::TournamentTooLarge,
+            error_code(alice.request_tournament_async(&crowd, TournamentFormat::RoundRobin, WordSource::Players, MatchMode::Classic).await));
+        drop(others);
+
+        let players = [alice.id, bob.id, carol.id];
+        alice.request_tournament_async(&players, TournamentFormat::SingleElimination, WordSource::Players, MatchMode::Classic)
+            .await
+            .unwrap();
+        assert_eq!(
+            ErrorCode::TournamentInProgress,
+            error_code(bob.request_tournament_async(&players, TournamentFormat::RoundRobin, WordSource::Players, MatchMode::Classic).await));
+        assert!(bob.send_tournament_word_async("test").await.is_err());
+        assert_eq!(ErrorCode::WordTooShort, error_code(alice.send_tournament_word_async("a").await));
+        assert_eq!(ErrorCode::TournamentInProgress, error_code(carol.create_room_async("elsewhere", "").await));
+
+        // carol gets a bye in the first round, players set words for each other in turns
+        play(&mut alice, &mut bob, "test", "").await;
+        play(&mut bob, &mut alice, "word", "nope").await;
+        play(&mut bob, &mut carol, "game", "").await;
+        play(&mut carol, &mut bob, "play", "pray").await;
+
+        let winner = StreamedMessage::TournamentWinner(Some(carol.id));
+        expect(&mut bob, winner.clone()).await;
+        expect(&mut carol, winner.clone()).await;
+
+        // the final bracket comes right before the winner
+        let mut pairings = Vec::new();
+        loop {
+            match alice.read_streamed_message_async().await.unwrap() {
+                Some(StreamedMessage::TournamentBracket(bracket)) => pairings = bracket,
+                Some(message) if message == winner => break,
+                Some(_) => continue,
+                None => panic!("expected the winner"),
+            }
+        }
+        assert_eq!(2, pairings.len());
+        assert!(pairings.iter().all(|p| p.state == PairingState::Finished));
+        assert_eq!((2, Some(carol.id), Some(900), Some(1000)),
+            (pairings[1].round, pairings[1].winner, pairings[1].first_score, pairings[1].second_score));
+
+        // a player leaving mid-round forfeits, the winner of its pairing meets the player with the bye
+        let mut dave = Client::connect_async(addr, PASSWORD).await.unwrap();
+        let erin = Client::connect_async(addr, PASSWORD).await.unwrap();
+        let frank = Client::connect_async(addr, PASSWORD).await.unwrap();
+        dave.request_tournament_async(&[dave.id, erin.id, frank.id], TournamentFormat::SingleElimination, WordSource::Players, MatchMode::Classic)
+            .await
+            .unwrap();
+        expect(&mut dave, StreamedMessage::TournamentWordRequested(erin.id)).await;
+        drop(erin);
+
+        // wait for the server to see the disconnect
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        expect(&mut dave, StreamedMessage::TournamentWordRequested(frank.id)).await;
+
+        // a challenger that doesn't send its word loses the pairing
+        let mut config = ServerConfig::new(PASSWORD);
+        config.rules.tournament = TournamentRules { word_timeout: std::time::Duration::from_millis(200) };
+        _ = tokio::spawn(async move {
+            _ = run_with_config_async("127.0.0.1:8109", config).await;
+        });
+
+        //wait for the server to start
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let addr = "127.0.0.1:8109".parse().unwrap();
+        let mut dave = Client::connect_async(addr, PASSWORD).await.unwrap();
+        let mut erin = Client::connect_async(addr, PASSWORD).await.unwrap();
+        dave.request_tournament_async(&[dave.id, erin.id], TournamentFormat::SingleElimination, WordSource::Players, MatchMode::Classic)
+            .await
+            .unwrap();
+        expect(&mut dave, StreamedMessage::TournamentWordRequested(erin.id)).await;
+
+        // timeouts are checked once a second
+        tokio::time::sleep(tokio::time::Duration::from_millis(1300)).await;
+        let winner = StreamedMessage::TournamentWinner(Some(erin.id));
+        expect(&mut erin, winner).await;
+        assert!(dave.send_tournament_word_async("late").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn tls() {
+        use rcgen::{BasicConstraints, CertificateParams, IsCa, KeyPair};
+
+        let dir = std::env::temp_dir().join(format!("guess_tls_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // a local CA and a server certificate issued by it
+        let mut ca_params = CertificateParams::new(Vec::new()).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca_key = KeyPair::generate().unwrap();
+        let ca = ca_params.self_signed(&ca_key).unwrap();
+        let server_key = KeyPair::generate().unwrap();
+        let server_cert = CertificateParams::new(vec!["localhost".to_string()])
+            .unwrap()
+            .signed_by(&server_key, &ca, &ca_key)
+            .unwrap();
+        let other_cert = CertificateParams::new(vec!["localhost".to_string()])
+            .unwrap()
+            .self_signed(&KeyPair::generate().unwrap())
+            .unwrap();
+
+        let write = |name: &str, pem: String| {
+            let path = dir.join(name);
+            std::fs::write(&path, pem).unwrap();
+            path
+        };
+        let ca_path = write("ca.pem", ca.pem());
+        let cert_path = write("server.pem", server_cert.pem());
+        let key_path = write("server.key", server_key.serialize_pem());
+        let other_path = write("other.pem", other_cert.pem());
+
+        let mut config = ServerConfig::new(PASSWORD);
+        config.tls = Some(ServerTlsConfig { cert_path: cert_path.clone(), key_path });
+        _ = tokio::spawn(async move {
+            _ = run_with_config_async("127.0.0.1:8097", config).await;
+        });
+
+        //wait for the server to start
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let addr = "127.0.0.1:8097".parse().unwrap();
+        let tls = |trust| ClientTlsConfig { server_name: "localhost".to_string(), trust };
+
+        let mut alice = Client::connect_tls_async(addr, &tls(ServerTrust::Ca(ca_path.clone())), PASSWORD, "alice", ACCOUNT_PASSWORD).await.unwrap();
+        let mut bob = Client::connect_tls_async(addr, &tls(ServerTrust::Pinned(cert_path)), PASSWORD, "bob", ACCOUNT_PASSWORD).await.unwrap();
+        alice.request_match_async(bob.id, "test").await.unwrap();
+        assert_eq!(Some(StreamedMessage::Challenged), bob.read_streamed_message_async().await.unwrap());
+        assert!(bob.send_attempt_async("test").await.unwrap());
+
+        // certificates that aren't trusted are rejected
+        assert!(Client::connect_tls_async(addr, &tls(ServerTrust::Pinned(other_path.clone())), PASSWORD, "", "").await.is_err());
+        assert!(Client::connect_tls_async(addr, &tls(ServerTrust::Ca(other_path)), PASSWORD, "", "").await.is_err());
+        let wrong_name = ClientTlsConfig { server_name: "example.com".to_string(), trust: ServerTrust::Ca(ca_path) };
+        assert!(Client::connect_tls_async(addr, &wrong_name, PASSWORD, "", "").await.is_err());
+
+        _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn websocket() {
+        use crate::bin_reader::BinReader;
+        use crate::bin_writer::BinWriter;
+        use crate::client::ClientMessage;
+        use crate::server::ServerMessage;
+        use futures::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::Message;
+
+        let mut config = ServerConfig::new(PASSWORD);
+        config.websocket_addr = Some("127.0.0.1:8099".parse().unwrap());
+        _ = tokio::spawn(async move {
+            _ = run_with_config_async("127.0.0.1:8098", config).await;
+        });
+
+        //wait for the server to start
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let (mut websocket, _) = tokio_tungstenite::connect_async("ws://127.0.0.1:8099").await.unwrap();
+        let Some(Ok(Message::Binary(data))) = websocket.next().await else { panic!("expected a binary message") };
+        assert_eq!(ServerMessage::RequestAuthorization as u8, BinReader::from_bytes(&data).read_u8().unwrap());
+
+        let mut bin_writer = BinWriter::with_capacity(64);
+        bin_writer.write_u8(ClientMessage::Authorization as u8);
+        bin_writer.write_str(PASSWORD);
+        bin_writer.write_str("browser");
+        bin_writer.write_str(ACCOUNT_PASSWORD);
+        websocket.send(Message::Binary(bin_writer.clone_data().into())).await.unwrap();
+        let Some(Ok(Message::Binary(data))) = websocket.next().await else { panic!("expected a binary message") };
+        let websocket_id = BinReader::from_bytes(&data).read_u32().unwrap();
+
+        // both transports share one game
+        let mut alice = Client::connect_as_async("127.0.0.1:8098".parse().unwrap(), PASSWORD, "alice", ACCOUNT_PASSWORD).await.unwrap();
+        assert_eq!(vec![websocket_id], alice.get_opponents_async().await.unwrap());
+        alice.request_match_async(websocket_id, "test").await.unwrap();
+        let Some(Ok(Message::Binary(data))) = websocket.next().await else { panic!("expected a binary message") };
+        assert_eq!(ServerMessage::Challenged as u8, BinReader::from_bytes(&data).read_u8().unwrap());
+    }
+
+    #[tokio::test]
+    async fn memory_transport() {
+        let (acceptor, connector) = MemoryAcceptor::new();
+        _ = tokio::spawn(async move {
+            _ = run_with_acceptor_async(acceptor, ServerConfig::new(PASSWORD)).await;
+        });
+
+        let mut alice = Client::connect_stream_async(connector.connect().unwrap(), PASSWORD, "alice", ACCOUNT_PASSWORD).await.unwrap();
+        let mut bob = Client::connect_stream_async(connector.connect().unwrap(), PASSWORD, "bob", ACCOUNT_PASSWORD).await.unwrap();
+        assert_eq!(vec![bob.id], alice.get_opponents_async().await.unwrap());
+
+        alice.request_match_async(bob.id, "test").await.unwrap();
+        assert_eq!(Some(StreamedMessage::Challenged), bob.read_streamed_message_async().await.unwrap());
+        assert!(bob.send_attempt_async("test").await.unwrap());
+
+        assert!(Client::connect_stream_async(connector.connect().unwrap(), "wrong", "", "").await.is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unix_socket() {
+        let path = std::env::temp_dir().join(format!("guess_unix_{}.sock", std::process::id()));
+        let mut config = ServerConfig::new(PASSWORD);
+        config.unix_path = Some(path.clone());
+        _ = tokio::spawn(async move {
+            _ = run_with_config_async("127.0.0.1:8100", config).await;
+        });
+
+        //wait for the server to start
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        // a bot on the socket plays a TCP challenger
+        let mut challenger = Client::connect_async("127.0.0.1:8100".parse().unwrap(), PASSWORD).await.unwrap();
+        let mut config = BotConfig::new("127.0.0.1:1".parse().unwrap(), PASSWORD);
+        config.unix_path = Some(path.clone());
+        config.max_matches = Some(1);
+        let guesser = tokio::spawn(run_bot_async(config, GuesserBot::new(FilteringStrategy::new(vec!["test".to_string()]))));
+
+        let mut opponents = Vec::new();
+        while opponents.is_empty() {
+            tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+            opponents = challenger.get_opponents_async().await.unwrap();
+        }
+        challenger.request_match_async(opponents[0], "test").await.unwrap();
+
+        let timeout = tokio::time::Duration::from_secs(5);
+        let guesser = tokio::time::timeout(timeout, guesser).await.unwrap().unwrap().unwrap();
+        assert_eq!(guesser.solved, 1);
+
+        let mut client = Client::connect_unix_async(&path, PASSWORD, "alice", ACCOUNT_PASSWORD).await.unwrap();
+        assert_eq!(vec![challenger.id], client.get_opponents_async().await.unwrap());
+
+        _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn json_protocol() {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let mut config = ServerConfig::new(PASSWORD);
+        config.json_addr = Some("127.0.0.1:8102".parse().unwrap());
+        _ = tokio::spawn(async move {
+            _ = run_with_config_async("127.0.0.1:8101", config).await;
+        });
+
+        //wait for the server to start
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let stream = tokio::net::TcpStream::connect("127.0.0.1:8102").await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        let mut read_json = async || -> serde_json::Value {
+            serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap()
+        };
+
+        assert_eq!(serde_json::json!({ "type": "RequestAuthorization" }), read_json().await);
+        writer.write_all(b"{\"type\":\"Authorization\",\"password\":\"password\",\"name\":\"carol\",\"account_password\":\"secret\"}\n").await.unwrap();
+        let authorized = read_json().await;
+        assert_eq!("Authorized", authorized["type"]);
+        let carol_id = authorized["id"].as_u64().unwrap() as u32;
+
+        // the same game as binary clients
+        let mut alice = Client::connect_as_async("127.0.0.1:8101".parse().unwrap(), PASSWORD, "alice", ACCOUNT_PASSWORD).await.unwrap();
+        alice.request_match_async(carol_id, "test").await.unwrap();
+        assert_eq!(serde_json::json!({ "type": "Challenged" }), read_json().await);
+
+        writer.write_all(b"{\"type\":\"SendAttempt\",\"word\":\"test\"}\n").await.unwrap();
+        assert_eq!(serde_json::json!({ "type": "AttemptResult", "correct": true, "letters": [] }), read_json().await);
+        let ended = read_json().await;
+        assert_eq!(("MatchEnded", 0), (ended["type"].as_str().unwrap(), ended["outcome"].as_u64().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn capture() {
+        let path = std::env::temp_dir().join(format!("guess_capture_{}.bin", std::process::id()));
+        _ = std::fs::remove_file(&path);
+
+        let mut config = ServerConfig::new(PASSWORD);
+        config.rules.scoring.second_penalty = 0;
+        config.capture_path = Some(path.clone());
+        _ = tokio::spawn(async move {
+            _ = run_with_config_async("127.0.0.1:8103", config).await;
+        });
+
+        //wait for the server to start
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let addr = "127.0.0.1:8103".parse().unwrap();
+        let room_password = "hideout";
+        let mut alice = Client::connect_as_async(addr, PASSWORD, "alice", ACCOUNT_PASSWORD).await.unwrap();
+        let mut bob = Client::connect_as_async(addr, PASSWORD, "bob", ACCOUNT_PASSWORD).await.unwrap();
+        alice.create_room_async("den", room_password).await.unwrap();
+        bob.join_room_async("den", room_password).await.unwrap();
+        alice.request_match_async(bob.id, "test").await.unwrap();
+        assert_eq!(Some(StreamedMessage::Challenged), bob.read_streamed_message_async().await.unwrap());
+        assert!(!bob.send_attempt_async("nope").await.unwrap());
+        assert!(bob.send_attempt_async("test").await.unwrap());
+        drop((alice, bob));
+
+        // wait for the server to see both disconnects
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let records = read_capture(&path).unwrap();
+        let lines = describe_capture(&records);
+        assert!(lines[0].ends_with("client 1 Connected"));
+        assert!(lines.iter().any(|l| l.ends_with(r#"client 2 Inbound {"type":"SendAttempt","word":"nope"}"#)));
+        assert!(lines.iter().any(|l| l.ends_with(r#"client 1 Outbound {"id":1,"type":"Authorized"}"#)));
+        assert_eq!(2, records.iter().filter(|r| r.kind == CaptureKind::Disconnected).count());
+
+        // passwords are blanked out before they are written
+        assert!(lines.iter().any(|l| l.ends_with(r#"client 1 Inbound {"account_password":"","name":"alice","password":"","type":"Authorization"}"#)));
+        assert!(lines.iter().any(|l| l.ends_with(r#"client 2 Inbound {"name":"den","password":"","type":"JoinRoom"}"#)));
+        let data = std::fs::read(&path).unwrap();
+        for password in [PASSWORD, ACCOUNT_PASSWORD, room_password] {
+            assert!(!data.windows(password.len()).any(|w| w == password.as_bytes()), "{}", password);
+        }
+
+        // the same conversation against a fresh server
+        let mut config = ServerConfig::new(PASSWORD);
+        config.rules.scoring.second_penalty = 0;
+        _ = tokio::spawn(async move {
+            _ = run_with_config_async("127.0.0.1:8104", config).await;
+        });
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let passwords = ReplayPasswords {
+            server: PASSWORD.to_string(),
+            account: "replayed".to_string(),
+            room: "replayed".to_string(),
+            ..Default::default()
+        };
+        let report = replay_capture_async(&records, "127.0.0.1:8104".parse().unwrap(), &passwords).await.unwrap();
+        assert_eq!(Vec::<ReplayMismatch>::new(), report.mismatches);
+        assert_eq!(records.iter().filter(|r| r.kind == CaptureKind::Outbound).count(), report.frames_compared);
+
+        _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn malformed_frames() {
+        use crate::bin_writer::BinWriter;
+        use crate::client::ClientMessage;
+        use crate::server::ServerMessage;
+        use crate::utils::create_framed_stream;
+        use futures::{SinkExt, StreamExt};
+        use tokio_util::bytes::Bytes;
+
+        let (acceptor, connector) = MemoryAcceptor::new();
+        _ = tokio::spawn(async move {
+            _ = run_with_acceptor_async(acceptor, ServerConfig::new(PASSWORD)).await;
+        });
+
+        let mut stream = create_framed_stream(connector.connect().unwrap());
+        stream.next().await.unwrap().unwrap();
+
+        // nothing but the authorization is processed before it
+        stream.send(Bytes::copy_from_slice(&[ClientMessage::ListOfOpponents as u8])).await.unwrap();
+        let response = stream.next().await.unwrap().unwrap();
+        assert_eq!(&[ServerMessage::Err as u8, ErrorCode::NotAuthorized as u8], &response[..]);
+
+        let mut bin_writer = BinWriter::with_capacity(64);
+        bin_writer.write_u8(ClientMessage::Authorization as u8);
+        bin_writer.write_str(PASSWORD);
+        bin_writer.write_str("mallory");
+        bin_writer.write_str(ACCOUNT_PASSWORD);
+        stream.send(Bytes::from(bin_writer.clone_data())).await.unwrap();
+        stream.next().await.unwrap().unwrap();
+
+        // the name can't be changed by authorizing again
+        stream.send(Bytes::from(bin_writer.clone_data())).await.unwrap();
+        let response = stream.next().await.unwrap().unwrap();
+        assert_eq!(&[ServerMessage::Err as u8, ErrorCode::AlreadyAuthorized as u8], &response[..]);
+
+        // frames that used to panic the server loop while being read
+        let frames: [&[u8]; 6] = [
+            &[],
+            &[200],
+            &[ClientMessage::RequestMatch as u8, 1],
+            &[ClientMessage::SendChat as u8, 2, 0, 0xff, 0xfe],
+            &[ClientMessage::SendAttempt as u8, 0xff, 0xff, b'a'],
+            &[ClientMessage::RequestRace as u8, 0, 0, 0, 0xff, 0xff, 1, 0, 0, 0],
+        ];
+        for frame in frames {
+            stream.send(Bytes::copy_from_slice(frame)).await.unwrap();
+            let response = stream.next().await.unwrap().unwrap();
+            assert_eq!(ServerMessage::Err as u8, response[0]);
+        }
+
+        let mut client = Client::connect_stream_async(connector.connect().unwrap(), PASSWORD, "alice", ACCOUNT_PASSWORD).await.unwrap();
+        assert_eq!(1, client.get_opponents_async().await.unwrap().len());
+    }
+
+    #[tokio::test]
+    async fn rate_limits() {
+        let mut config = ServerConfig::new(PASSWORD);
+        config.limits = RateLimits {
+            messages: Some(RateLimit { burst: 3, per_second: 1 }),
+            ip_messages: None,
+            ip_connections: None,
+            max_ip_connections: Some(2),
+            max_violations: Some(2),
+        };
+        let capture_path = std::env::temp_dir().join(format!("guess_rate_limits_capture_{}.bin", std::process::id()));
+        _ = std::fs::remove_file(&capture_path);
+        config.capture_path = Some(capture_path.clone());
+        _ = tokio::spawn(async move {
+            _ = run_with_config_async("127.0.0.1:8105", config).await;
+        });
+
+        //wait for the server to start
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let addr = "127.0.0.1:8105".parse().unwrap();
+        let mut alice = Client::connect_async(addr, PASSWORD).await.unwrap();
+        let _bob = Client::connect_async(addr, PASSWORD).await.unwrap();
+        assert!(Client::connect_async(addr, PASSWORD).await.is_err());
+
+        // authorizing took the first token
+        alice.get_opponents_async().await.unwrap();
+        alice.get_opponents_async().await.unwrap();
+        let err = alice.get_opponents_async().await.unwrap_err();
+        assert_eq!(ErrorCode::RateLimited, err.downcast_ref::<GameError>().unwrap().code);
+
+        // the throttled message and its answer are captured although they never reach the server loop
+        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+        let records: Vec<_> = read_capture(&capture_path).unwrap().into_iter().filter(|r| r.client_id == alice.id).collect();
+        assert_eq!(4, records.iter().filter(|r| r.kind == CaptureKind::Inbound).count());
+        let throttled = [crate::server::ServerMessage::Err as u8, ErrorCode::RateLimited as u8];
+        assert_eq!(Some(&throttled[..]), records.last().map(|r| &r.frame[..]));
+        _ = std::fs::remove_file(&capture_path);
+
+        // the second throttled message in a row drops the connection
+        assert!(alice.get_opponents_async().await.is_err());
+        assert!(alice.get_opponents_async().await.is_err());
+
+        // wait for the server to see the disconnect
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        Client::connect_async(addr, PASSWORD).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn authorization_lockout() {
+        const ADMIN_PASSWORD: &str = "admin";
+
+        let mut config = ServerConfig::new(PASSWORD);
+        config.admin_password = Some(ADMIN_PASSWORD.to_string());
+        config.lockout = LockoutRules {
+            max_failures: Some(2),
+            lockout: std::time::Duration::from_secs(60),
+            max_lockout: std::time::Duration::from_secs(60 * 60),
+        };
+        _ = tokio::spawn(async move {
+            _ = run_with_config_async("127.0.0.1:8106", config).await;
+        });
+
+        //wait for the server to start
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let addr = "127.0.0.1:8106".parse().unwrap();
+        let mut admin = Client::connect_async(addr, PASSWORD).await.unwrap();
+        let ip = "127.0.0.1".parse().unwrap();
+        let err = admin.clear_lockout_async(ip).await.unwrap_err();
+        assert_eq!(ErrorCode::NotAdmin, err.downcast_ref::<GameError>().unwrap().code);
+        admin.authorize_admin_async(ADMIN_PASSWORD).await.unwrap();
+
+        assert!(Client::connect_async(addr, "wrong").await.is_err());
+        assert!(Client::connect_async(addr, "wrong").await.is_err());
+
+        // even the right password is refused now
+        let err = Client::connect_async(addr, PASSWORD).await.err().unwrap();
+        assert_eq!(ErrorCode::LockedOut, err.downcast_ref::<GameError>().unwrap().code);
+        let err = admin.authorize_admin_async(ADMIN_PASSWORD).await.unwrap_err();
+        assert_eq!(ErrorCode::LockedOut, err.downcast_ref::<GameError>().unwrap().code);
+
+        admin.clear_lockout_async(ip).await.unwrap();
+        Client::connect_async(addr, PASSWORD).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn admin_commands() {
+        const ADMIN_PASSWORD: &str = "admin";
+
+        let mut config = ServerConfig::new(PASSWORD);
+        config.admin_password = Some(ADMIN_PASSWORD.to_string());
+        config.limits.ip_connections = None;
+        _ = tokio::spawn(async move {
+            _ = run_with_config_async("127.0.0.1:8107", config).await;
+        });
+
+        //wait for the server to start
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let addr = "127.0.0.1:8107".parse().unwrap();
+        let ip = "127.0.0.1".parse().unwrap();
+        let mut admin = Client::connect_async(addr, PASSWORD).await.unwrap();
+        let mut alice = Client::connect_as_async(addr, PASSWORD, "alice", ACCOUNT_PASSWORD).await.unwrap();
+        let mut bob = Client::connect_as_async(addr, PASSWORD, "bob", ACCOUNT_PASSWORD).await.unwrap();
+
+        let err = alice.kick_async(bob.id).await.unwrap_err();
+        assert_eq!(ErrorCode::NotAdmin, err.downcast_ref::<GameError>().unwrap().code);
+        let err = admin.authorize_admin_async("wrong").await.unwrap_err();
+        assert_eq!(ErrorCode::NotAdmin, err.downcast_ref::<GameError>().unwrap().code);
+        admin.authorize_admin_async(ADMIN_PASSWORD).await.unwrap();
+
+        alice.request_match_async(bob.id, "test").await.unwrap();
+        assert_eq!(Some(StreamedMessage::Challenged), bob.read_streamed_message_async().await.unwrap());
+
+        let connections = admin.get_connections_async().await.unwrap();
+        assert_eq!(
+            vec![(admin.id, "", false, true), (alice.id, "alice", true, false), (bob.id, "bob", true, false)],
+            connections.iter().map(|c| (c.id, c.name.as_str(), c.in_match, c.admin)).collect::<Vec<_>>());
+        assert!(connections.iter().all(|c| c.ip == Some(ip) && c.room == LOBBY_ROOM));
+
+        let matches = admin.get_matches_async().await.unwrap();
+        assert_eq!(vec![(alice.id, bob.id)], matches.iter().map(|m| (m.challenger_id, m.opponent_id)).collect::<Vec<_>>());
+
+        // lists are cut to what fits into a single packet
+        let mut crowd = Vec::new();
+        for i in 0..20 {
+            let mut client = Client::connect_async(addr, PASSWORD).await.unwrap();
+            client.create_room_async(&format!("{:0>32}", i), "").await.unwrap();
+            crowd.push(client);
+        }
+        let connections = admin.get_connections_async().await.unwrap();
+        assert!(connections.len() > 3 && connections.len() < 23);
+        assert_eq!(vec![admin.id, alice.id, bob.id], connections.iter().take(3).map(|c| c.id).collect::<Vec<_>>());
+        drop(crowd);
+
+        admin.end_match_async(bob.id).await.unwrap();
+        for client in [&mut alice, &mut bob] {
+            match client.read_streamed_message_async().await.unwrap() {
+                Some(StreamedMessage::MatchEnded(score)) => assert_eq!(MatchOutcome::Aborted, score.outcome),
+                other => panic!("Unexpected message {:?}", other),
+            }
+        }
+        assert!(admin.get_matches_async().await.unwrap().is_empty());
+
+        admin.broadcast_async("Restarting soon").await.unwrap();
+        for client in [&mut alice, &mut bob] {
+            match client.read_streamed_message_async().await.unwrap() {
+                Some(StreamedMessage::Chat(message)) => assert_eq!((SERVER_ID, "Restarting soon"), (message.sender_id, message.text.as_str())),
+                other => panic!("Unexpected message {:?}", other),
+            }
+        }
+
+        let err = admin.kick_async(admin.id).await.unwrap_err();
+        assert_eq!(ErrorCode::CannotKickSelf, err.downcast_ref::<GameError>().unwrap().code);
+
+        // kicking ends the player's match and removes it right away
+        alice.request_match_async(bob.id, "test").await.unwrap();
+        assert_eq!(Some(StreamedMessage::Challenged), bob.read_streamed_message_async().await.unwrap());
+        admin.kick_async(bob.id).await.unwrap();
+        match alice.read_streamed_message_async().await.unwrap() {
+            Some(StreamedMessage::MatchEnded(score)) => assert_eq!(MatchOutcome::OpponentLeft, score.outcome),
+            other => panic!("Unexpected message {:?}", other),
+        }
+        assert!(admin.get_connections_async().await.unwrap().iter().all(|c| c.id != bob.id));
+        assert!(!alice.get_opponents_async().await.unwrap().contains(&bob.id));
+        assert!(bob.get_opponents_async().await.is_err());
+
+        // banning the account kicks alice and keeps her out
+        let ban = Ban::Account("alice".to_string());
+        admin.ban_async(&ban).await.unwrap();
+        assert!(admin.get_connections_async().await.unwrap().iter().all(|c| c.id != alice.id));
+        assert!(alice.get_opponents_async().await.is_err());
+        let err = Client::connect_as_async(addr, PASSWORD, "alice", ACCOUNT_PASSWORD).await.err().unwrap();
+        assert_eq!(ErrorCode::Banned, err.downcast_ref::<GameError>().unwrap().code);
+        admin.unban_async(&ban).await.unwrap();
+        Client::connect_as_async(addr, PASSWORD, "alice", ACCOUNT_PASSWORD).await.unwrap();
+
+        // banned IPs can't even connect, the admin issuing the ban stays
+        admin.ban_async(&Ban::Ip(ip)).await.unwrap();
+        assert!(Client::connect_async(addr, PASSWORD).await.is_err());
+        admin.unban_async(&Ban::Ip(ip)).await.unwrap();
+        Client::connect_async(addr, PASSWORD).await.unwrap();
+    }
 }
@@ -0,0 +1,148 @@
+use std::io;
+use std::net::IpAddr;
+#[cfg(unix)]
+use std::os::unix::fs::FileTypeExt;
+#[cfg(unix)]
+use std::path::Path;
+use futures::channel::mpsc::{UnboundedReceiver, UnboundedSender};
+use futures::future::{self, BoxFuture};
+use futures::{FutureExt, StreamExt};
+use tokio::io::DuplexStream;
+use tokio::net::{TcpListener, ToSocketAddrs};
+#[cfg(unix)]
+use tokio::net::UnixListener;
+use crate::utils::{BoxedStream, MAX_PACKET_LENGTH};
+
+/// Protocol spoken on top of an accepted stream.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Transport {
+    /// Frames prefixed with their length.
+    Framed,
+    /// A binary WebSocket message per frame, for browser clients.
+    WebSocket,
+    /// The same messages as newline-delimited JSON, for debugging and scripts.
+    Json,
+}
+
+/// A stream accepted by an `Acceptor`, TLS is layered on top by the server when configured.
+pub struct Connection {
+    pub stream: BoxedStream,
+    pub transport: Transport,
+    /// Address rate limits per IP apply to, `None` for local connections.
+    pub peer_ip: Option<IpAddr>,
+}
+
+/// Source of new connections for the server.
+pub trait Acceptor: Send {
+    fn accept_async(&mut self) -> BoxFuture<'_, io::Result<Connection>>;
+}
+
+/// Accepts TCP connections speaking the given transport.
+pub struct TcpAcceptor {
+    listener: TcpListener,
+    transport: Transport,
+}
+
+impl TcpAcceptor {
+    pub async fn bind<A: ToSocketAddrs>(addr: A, transport: Transport) -> io::Result<TcpAcceptor> {
+        Ok(TcpAcceptor {
+            listener: TcpListener::bind(addr).await?,
+            transport,
+        })
+    }
+}
+
+impl Acceptor for TcpAcceptor {
+    fn accept_async(&mut self) -> BoxFuture<'_, io::Result<Connection>> {
+        async move {
+            let (stream, addr) = self.listener.accept().await?;
+            Ok(Connection { stream: Box::new(stream), transport: self.transport, peer_ip: Some(addr.ip()) })
+        }.boxed()
+    }
+}
+
+/// Accepts from whichever acceptor has a connection first.
+impl Acceptor for Vec<Box<dyn Acceptor>> {
+    fn accept_async(&mut self) -> BoxFuture<'_, io::Result<Connection>> {
+        if self.is_empty() {
+            return future::pending().boxed();
+        }
+
+        future::select_all(self.iter_mut().map(|acceptor| acceptor.accept_async()))
+            .map(|(result, _, _)| result)
+            .boxed()
+    }
+}
+
+/// Accepts connections on a Unix domain socket, for bots and sidecars on the same host.
+#[cfg(unix)]
+pub struct UnixAcceptor {
+    listener: UnixListener,
+}
+
+#[cfg(unix)]
+impl UnixAcceptor {
+    /// Binds the socket at `path`, replacing a socket left behind by a previous run.
+    pub fn bind<P: AsRef<Path>>(path: P) -> io::Result<UnixAcceptor> {
+        let path = path.as_ref();
+        if std::fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_socket()) {
+            std::fs::remove_file(path)?;
+        }
+
+        Ok(UnixAcceptor {
+            listener: UnixListener::bind(path)?,
+        })
+    }
+}
+
+#[cfg(unix)]
+impl Acceptor for UnixAcceptor {
+    fn accept_async(&mut self) -> BoxFuture<'_, io::Result<Connection>> {
+        async move {
+            let (stream, _) = self.listener.accept().await?;
+            Ok(Connection { stream: Box::new(stream), transport: Transport::Framed, peer_ip: None })
+        }.boxed()
+    }
+}
+
+/// In-memory connections over `tokio::io::duplex` pipes, created with `MemoryAcceptor::new`.
+pub struct MemoryAcceptor {
+    receiver: UnboundedReceiver<DuplexStream>,
+}
+
+/// Opens connections to the paired `MemoryAcceptor`.
+#[derive(Clone)]
+pub struct MemoryConnector {
+    sender: UnboundedSender<DuplexStream>,
+}
+
+impl MemoryAcceptor {
+    pub fn new() -> (MemoryAcceptor, MemoryConnector) {
+        let (sender, receiver) = futures::channel::mpsc::unbounded();
+        (MemoryAcceptor { receiver }, MemoryConnector { sender })
+    }
+}
+
+impl Acceptor for MemoryAcceptor {
+    fn accept_async(&mut self) -> BoxFuture<'_, io::Result<Connection>> {
+        async move {
+            match self.receiver.next().await {
+                Some(stream) => Ok(Connection { stream: Box::new(stream), transport: Transport::Framed, peer_ip: None }),
+                // every connector is gone, nothing will connect anymore
+                None => future::pending().await,
+            }
+        }.boxed()
+    }
+}
+
+impl MemoryConnector {
+    /// Returns the client end of a new connection.
+    pub fn connect(&self) -> io::Result<DuplexStream> {
+        let (client, server) = tokio::io::duplex(MAX_PACKET_LENGTH * 4);
+        self.sender
+            .unbounded_send(server)
+            .map_err(|_| io::Error::new(io::ErrorKind::ConnectionRefused, "The server isn't running"))?;
+
+        Ok(client)
+    }
+}
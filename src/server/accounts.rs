@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use anyhow::anyhow;
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use crate::bin_reader::BinReader;
+use crate::bin_writer::BinWriter;
+
+const SALT_LENGTH: usize = 16;
+const HASH_LENGTH: usize = 32;
+
+/// Hashing runs on the blocking thread pool, failed attempts are limited by the authorization lockout.
+const PBKDF2_ITERATIONS: u32 = 10_000;
+
+struct Credential {
+    salt: [u8; SALT_LENGTH],
+    hash: [u8; HASH_LENGTH],
+}
+
+/// Account names with the password protecting them, an account is registered the first time
+/// its name is used. Rewritten to disk after every registration.
+pub struct Accounts {
+    path: Option<PathBuf>,
+    credentials: HashMap<String, Credential>,
+    random: SystemRandom,
+}
+
+impl Accounts {
+    pub fn load(path: Option<PathBuf>) -> anyhow::Result<Accounts> {
+        let mut credentials = HashMap::new();
+
+        if let Some(path) = &path {
+            match std::fs::read(path) {
+                Ok(data) => {
+                    let mut bin_reader = BinReader::from_slice(&data);
+                    let count = bin_reader.read_u32()?;
+                    for _ in 0..count {
+                        let name = bin_reader.read_str()?;
+                        let mut credential = Credential { salt: [0; SALT_LENGTH], hash: [0; HASH_LENGTH] };
+                        for byte in credential.salt.iter_mut().chain(credential.hash.iter_mut()) {
+                            *byte = bin_reader.read_u8()?;
+                        }
+                        credentials.insert(name, credential);
+                    }
+                }
+                Err(err) if err.kind() == ErrorKind::NotFound => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(Accounts { path, credentials, random: SystemRandom::new() })
+    }
+
+    /// Checks the password of the account, unknown accounts are registered with it. Accounts
+    /// can't be registered without a password.
+    pub fn verify_or_register(&mut self, name: &str, password: &str) -> anyhow::Result<bool> {
+        if let Some(credential) = self.credentials.get(name) {
+            let verified = pbkdf2::verify(
+                pbkdf2::PBKDF2_HMAC_SHA256,
+                iterations(),
+                &credential.salt,
+                password.as_bytes(),
+                &credential.hash);
+            return Ok(verified.is_ok());
+        }
+
+        if password.is_empty() {
+            return Ok(false);
+        }
+
+        let mut credential = Credential { salt: [0; SALT_LENGTH], hash: [0; HASH_LENGTH] };
+        self.random.fill(&mut credential.salt).map_err(|_| anyhow!("Couldn't generate a salt"))?;
+        pbkdf2::derive(pbkdf2::PBKDF2_HMAC_SHA256, iterations(), &credential.salt, password.as_bytes(), &mut credential.hash);
+
+        self.credentials.insert(name.to_string(), credential);
+        self.save()?;
+        Ok(true)
+    }
+
+    /// Runs `verify_or_register` on the blocking thread pool so hashing doesn't hold up the
+    /// server loop, errors count as a failed verification.
+    pub async fn verify_or_register_async(accounts: Arc<Mutex<Accounts>>, name: String, password: String) -> bool {
+        let result = tokio::task::spawn_blocking(move || match accounts.lock() {
+            Ok(mut accounts) => accounts.verify_or_register(&name, &password),
+            Err(_) => Err(anyhow!("Accounts are poisoned")),
+        }).await;
+
+        match result {
+            Ok(Ok(verified)) => verified,
+            Ok(Err(err)) => {
+                eprintln!("Couldn't register account: {}", err);
+                false
+            }
+            Err(err) => {
+                eprintln!("Couldn't verify account: {}", err);
+                false
+            }
+        }
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let mut bin_writer = BinWriter::with_capacity(256);
+        bin_writer.write_u32(self.credentials.len() as u32);
+        for (name, credential) in &self.credentials {
+            bin_writer.write_str(name);
+            for byte in credential.salt.iter().chain(credential.hash.iter()) {
+                bin_writer.write_u8(*byte);
+            }
+        }
+
+        // write to a temporary file first so a crash can't leave a truncated file behind
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, bin_writer.clone_data())?;
+        std::fs::rename(&tmp_path, path)?;
+
+        Ok(())
+    }
+}
+
+fn iterations() -> NonZeroU32 {
+    NonZeroU32::new(PBKDF2_ITERATIONS).unwrap_or(NonZeroU32::MIN)
+}
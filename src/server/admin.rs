@@ -0,0 +1,172 @@
+use std::collections::HashSet;
+use std::net::IpAddr;
+use anyhow::bail;
+use crate::bin_reader::BinReader;
+use crate::bin_writer::BinWriter;
+use crate::server::error::{ErrorCode, GameError};
+use crate::server::game::MatchMode;
+use crate::server::lockout::Lockouts;
+use crate::utils::ClientId;
+
+/// Connection as it is listed to admins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectionInfo {
+    pub id: ClientId,
+    /// `None` for local connections.
+    pub ip: Option<IpAddr>,
+    /// Empty for players that didn't pick an account name.
+    pub name: String,
+    pub room: String,
+    pub in_match: bool,
+    pub admin: bool,
+}
+
+impl ConnectionInfo {
+    pub fn encoded_len(&self) -> usize {
+        4 + 2 + self.ip.map(|ip| ip.to_string().len()).unwrap_or_default() + 2 + self.name.len() + 2 + self.room.len() + 1 + 1
+    }
+
+    pub fn write(&self, bin_writer: &mut BinWriter) {
+        bin_writer.write_u32(self.id);
+        bin_writer.write_str(&self.ip.map(|ip| ip.to_string()).unwrap_or_default());
+        bin_writer.write_str(&self.name);
+        bin_writer.write_str(&self.room);
+        bin_writer.write_u8(self.in_match as u8);
+        bin_writer.write_u8(self.admin as u8);
+    }
+
+    pub fn read(bin_reader: &mut BinReader) -> anyhow::Result<ConnectionInfo> {
+        let id = bin_reader.read_u32()?;
+        let ip = match bin_reader.read_str()?.as_str() {
+            "" => None,
+            ip => Some(ip.parse()?),
+        };
+
+        Ok(ConnectionInfo {
+            id,
+            ip,
+            name: bin_reader.read_str()?,
+            room: bin_reader.read_str()?,
+            in_match: bin_reader.read_u8()? > 0,
+            admin: bin_reader.read_u8()? > 0,
+        })
+    }
+}
+
+/// Running match as it is listed to admins, without the word.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchInfo {
+    pub room: String,
+    pub challenger_id: ClientId,
+    pub opponent_id: ClientId,
+    pub mode: MatchMode,
+    pub started_at: u64,
+}
+
+impl MatchInfo {
+    pub fn encoded_len(&self) -> usize {
+        2 + self.room.len() + 4 + 4 + 1 + 8
+    }
+
+    pub fn write(&self, bin_writer: &mut BinWriter) {
+        bin_writer.write_str(&self.room);
+        bin_writer.write_u32(self.challenger_id);
+        bin_writer.write_u32(self.opponent_id);
+        bin_writer.write_u8(self.mode as u8);
+        bin_writer.write_u64(self.started_at);
+    }
+
+    pub fn read(bin_reader: &mut BinReader) -> anyhow::Result<MatchInfo> {
+        Ok(MatchInfo {
+            room: bin_reader.read_str()?,
+            challenger_id: bin_reader.read_u32()?,
+            opponent_id: bin_reader.read_u32()?,
+            mode: MatchMode::try_from(bin_reader.read_u8()?)?,
+            started_at: bin_reader.read_u64()?,
+        })
+    }
+}
+
+/// Account name or IP an admin bans.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ban {
+    Account(String),
+    Ip(IpAddr),
+}
+
+impl Ban {
+    pub fn write(&self, bin_writer: &mut BinWriter) {
+        match self {
+            Ban::Account(name) => {
+                bin_writer.write_u8(0);
+                bin_writer.write_str(name);
+            }
+            Ban::Ip(ip) => {
+                bin_writer.write_u8(1);
+                bin_writer.write_str(&ip.to_string());
+            }
+        }
+    }
+
+    pub fn read(bin_reader: &mut BinReader) -> anyhow::Result<Ban> {
+        let kind = bin_reader.read_u8()?;
+        let target = bin_reader.read_str()?;
+
+        match kind {
+            0 => Ok(Ban::Account(target)),
+            1 => Ok(Ban::Ip(target.parse()?)),
+            _ => bail!("Couldn't convert {} to Ban", kind),
+        }
+    }
+
+    /// Whether a connection from `ip` playing as `name` is banned by this.
+    pub fn matches(&self, ip: Option<IpAddr>, name: &str) -> bool {
+        match self {
+            Ban::Account(account) => !name.is_empty() && account == name,
+            Ban::Ip(banned) => ip == Some(*banned),
+        }
+    }
+}
+
+/// Bans only live in memory, they are gone once the server restarts.
+#[derive(Default)]
+pub struct Bans {
+    accounts: HashSet<String>,
+    ips: HashSet<IpAddr>,
+}
+
+impl Bans {
+    pub fn add(&mut self, ban: &Ban) {
+        match ban {
+            Ban::Account(name) => self.accounts.insert(name.clone()),
+            Ban::Ip(ip) => self.ips.insert(*ip),
+        };
+    }
+
+    /// Returns whether the ban existed.
+    pub fn remove(&mut self, ban: &Ban) -> bool {
+        match ban {
+            Ban::Account(name) => self.accounts.remove(name),
+            Ban::Ip(ip) => self.ips.remove(ip),
+        }
+    }
+
+    pub fn is_ip_banned(&self, ip: Option<IpAddr>) -> bool {
+        ip.is_some_and(|ip| self.ips.contains(&ip))
+    }
+
+    pub fn check(&self, ip: Option<IpAddr>, name: &str) -> anyhow::Result<()> {
+        if self.is_ip_banned(ip) || (!name.is_empty() && self.accounts.contains(name)) {
+            bail!(GameError::new(ErrorCode::Banned, "Banned from the server"));
+        }
+        Ok(())
+    }
+}
+
+/// Server-wide state admins act on next to the rooms and connections.
+pub struct Moderation {
+    pub lockouts: Lockouts,
+    pub bans: Bans,
+    // WARN: should be a hash of the password
+    pub admin_password: Option<String>,
+}
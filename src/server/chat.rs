@@ -0,0 +1,83 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+use anyhow::bail;
+use crate::bin_reader::BinReader;
+use crate::bin_writer::BinWriter;
+use crate::server::error::{ErrorCode, GameError};
+use crate::utils::{unix_millis, ClientId};
+
+#[derive(Debug, Clone)]
+pub struct ChatRules {
+    pub max_length: usize,
+    /// Messages a player can send within `window`, room and direct messages count together.
+    pub max_messages: usize,
+    pub window: Duration,
+}
+
+impl Default for ChatRules {
+    fn default() -> Self {
+        ChatRules {
+            max_length: 200,
+            max_messages: 5,
+            window: Duration::from_secs(10),
+        }
+    }
+}
+
+impl ChatRules {
+    /// Checks the message and records it in the sender's recent messages.
+    pub fn check(&self, sent: &mut VecDeque<u64>, text: &str) -> anyhow::Result<()> {
+        if text.trim().is_empty() {
+            bail!(GameError::new(ErrorCode::ChatEmpty, "Chat message is empty"));
+        }
+
+        if text.chars().count() > self.max_length {
+            bail!(GameError::new(
+                ErrorCode::ChatTooLong,
+                format!("Chat message is longer than {} characters", self.max_length)));
+        }
+
+        let now = unix_millis();
+        let window = self.window.as_millis() as u64;
+        while sent.front().is_some_and(|t| now.saturating_sub(*t) >= window) {
+            sent.pop_front();
+        }
+
+        if sent.len() >= self.max_messages {
+            bail!(GameError::new(
+                ErrorCode::ChatRateLimited,
+                format!("No more than {} messages every {}ms", self.max_messages, window)));
+        }
+
+        sent.push_back(now);
+        Ok(())
+    }
+}
+
+/// Chat message as it is pushed to the recipients.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatMessage {
+    pub sender_id: ClientId,
+    pub sender_name: String,
+    /// Whether the message was sent only to the recipient instead of the whole room.
+    pub direct: bool,
+    pub text: String,
+}
+
+impl ChatMessage {
+    pub fn write(&self, bin_writer: &mut BinWriter) {
+        bin_writer.write_u32(self.sender_id);
+        bin_writer.write_str(&self.sender_name);
+        bin_writer.write_u8(self.direct as u8);
+        bin_writer.write_str(&self.text);
+    }
+
+    pub fn read(bin_reader: &mut BinReader) -> anyhow::Result<ChatMessage> {
+        Ok(ChatMessage {
+            sender_id: bin_reader.read_u32()?,
+            sender_name: bin_reader.read_str()?,
+            direct: bin_reader.read_u8()? > 0,
+            text: bin_reader.read_str()?,
+        })
+    }
+}
@@ -0,0 +1,109 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+use crate::server::chat::ChatRules;
+use crate::server::limits::RateLimits;
+use crate::server::lockout::LockoutRules;
+use crate::server::dictionary::WordRules;
+use crate::server::hangman::HangmanRules;
+use crate::server::leak::LeakCheck;
+use crate::server::scoring::ScoringConfig;
+use crate::server::solo::SoloRules;
+use crate::server::text::AnswerNormalization;
+use crate::server::tournament::TournamentRules;
+use crate::tls::ServerTlsConfig;
+
+pub struct ServerConfig {
+    // WARN: should be a hash of the password
+    pub password: String,
+    /// Players that send it after authorizing can run admin commands, there are no admins when `None`.
+    pub admin_password: Option<String>,
+    /// File finished matches are appended to, history is disabled when `None`.
+    pub history_path: Option<PathBuf>,
+    /// File player ratings are persisted to, ratings only live in memory when `None`.
+    pub ratings_path: Option<PathBuf>,
+    /// File account passwords are persisted to, accounts only live in memory when `None` so
+    /// persisted history and ratings of a name can be claimed by someone else after a restart.
+    pub accounts_path: Option<PathBuf>,
+    /// Rules of the lobby and of the rooms players create.
+    pub rules: GameRules,
+    /// Rooms that exist next to the lobby from the start, they stay open while empty.
+    pub rooms: Vec<RoomConfig>,
+    /// Clients have to connect over TLS when set, WebSocket clients included.
+    pub tls: Option<ServerTlsConfig>,
+    /// Address of an additional listener for WebSocket clients, they play in the same rooms.
+    pub websocket_addr: Option<SocketAddr>,
+    /// Address of an additional listener speaking newline-delimited JSON instead of binary frames.
+    pub json_addr: Option<SocketAddr>,
+    /// Message and connection limits per connection and per source IP.
+    pub limits: RateLimits,
+    /// Lockout of IPs that keep sending wrong passwords.
+    pub lockout: LockoutRules,
+    /// Every frame in and out is appended to this file when set, see `describe_capture`.
+    pub capture_path: Option<PathBuf>,
+    /// Path of an additional Unix domain socket listener, only used on unix platforms.
+    pub unix_path: Option<PathBuf>,
+}
+
+/// Rules a single room's matches are played by.
+#[derive(Debug, Clone, Default)]
+pub struct GameRules {
+    pub scoring: ScoringConfig,
+    pub hints: HintRules,
+    pub words: WordRules,
+    pub answers: AnswerNormalization,
+    pub hangman: HangmanRules,
+    pub solo: SoloRules,
+    pub chat: ChatRules,
+    pub tournament: TournamentRules,
+}
+
+/// A room created by the server operator.
+#[derive(Debug, Clone)]
+pub struct RoomConfig {
+    pub name: String,
+    /// Players have to send it to join, the room is open when `None`.
+    pub password: Option<String>,
+    pub rules: GameRules,
+}
+
+/// Limits on the hints a challenger can send during a single match.
+#[derive(Debug, Clone)]
+pub struct HintRules {
+    pub max_hints: usize,
+    pub min_interval: Duration,
+    pub max_length: usize,
+    pub leak_check: LeakCheck,
+}
+
+impl Default for HintRules {
+    fn default() -> Self {
+        HintRules {
+            max_hints: 5,
+            min_interval: Duration::from_secs(1),
+            max_length: 100,
+            leak_check: LeakCheck::default(),
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn new(password: &str) -> ServerConfig {
+        ServerConfig {
+            password: password.to_string(),
+            admin_password: None,
+            history_path: None,
+            ratings_path: None,
+            accounts_path: None,
+            rules: GameRules::default(),
+            rooms: Vec::new(),
+            tls: None,
+            websocket_addr: None,
+            json_addr: None,
+            limits: RateLimits::default(),
+            lockout: LockoutRules::default(),
+            capture_path: None,
+            unix_path: None,
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use anyhow::bail;
+use crate::server::error::{ErrorCode, GameError};
+use crate::server::text::AnswerNormalization;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CharacterSet {
+    /// Letters of any script.
+    Alphabetic,
+    /// Only `a-z` and `A-Z`.
+    AsciiLetters,
+    /// Only the listed characters.
+    Custom(String),
+}
+
+impl CharacterSet {
+    fn contains(&self, c: char) -> bool {
+        match self {
+            CharacterSet::Alphabetic => c.is_alphabetic(),
+            CharacterSet::AsciiLetters => c.is_ascii_alphabetic(),
+            CharacterSet::Custom(chars) => chars.contains(c),
+        }
+    }
+}
+
+/// Which words a challenger is allowed to pick.
+#[derive(Debug, Clone)]
+pub struct WordRules {
+    /// Files with one word per line, lines starting with `#` are ignored.
+    /// Any word is accepted when no lists are given.
+    pub word_lists: Vec<PathBuf>,
+    pub min_length: usize,
+    pub max_length: usize,
+    pub allowed_characters: CharacterSet,
+}
+
+impl Default for WordRules {
+    fn default() -> Self {
+        WordRules {
+            word_lists: Vec::new(),
+            min_length: 2,
+            max_length: 32,
+            allowed_characters: CharacterSet::Alphabetic,
+        }
+    }
+}
+
+pub struct Dictionary {
+    rules: WordRules,
+    answers: AnswerNormalization,
+    // words from all lists normalized like answers, `None` when no lists are configured
+    words: Option<HashSet<String>>,
+}
+
+impl Dictionary {
+    pub fn load(rules: &WordRules, answers: &AnswerNormalization) -> anyhow::Result<Dictionary> {
+        let mut words = None;
+
+        for path in &rules.word_lists {
+            words
+                .get_or_insert_with(HashSet::new)
+                .extend(read_word_list(path)?.iter().map(|w| answers.apply(w)));
+        }
+
+        Ok(Dictionary {
+            rules: rules.clone(),
+            answers: answers.clone(),
+            words,
+        })
+    }
+
+    pub fn validate(&self, word: &str) -> anyhow::Result<()> {
+        let length = word.chars().count();
+        if length < self.rules.min_length {
+            bail!(GameError::new(
+                ErrorCode::WordTooShort,
+                format!("Word has to be at least {} characters long", self.rules.min_length)));
+        }
+
+        if length > self.rules.max_length {
+            bail!(GameError::new(
+                ErrorCode::WordTooLong,
+                format!("Word can't be longer than {} characters", self.rules.max_length)));
+        }
+
+        if !word.chars().all(|c| self.rules.allowed_characters.contains(c)) {
+            bail!(GameError::new(
+                ErrorCode::WordInvalidCharacters,
+                format!("Word '{}' contains characters that aren't allowed", word)));
+        }
+
+        if let Some(words) = &self.words {
+            if !words.contains(&self.answers.apply(word)) {
+                bail!(GameError::new(
+                    ErrorCode::WordNotInDictionary,
+                    format!("Word '{}' isn't in the dictionary", word)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads a file with one word per line, skipping empty lines and lines starting with `#`.
+pub fn read_word_list(path: &Path) -> anyhow::Result<Vec<String>> {
+    let content = std::fs::read_to_string(path)?;
+
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
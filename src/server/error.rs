@@ -0,0 +1,115 @@
+use std::fmt::{Display, Formatter};
+use anyhow::bail;
+
+/// Sent after the `Err` flag so clients can tell why a request failed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(u8)]
+pub enum ErrorCode {
+    Unknown,
+    HintLimitReached,
+    HintTooSoon,
+    HintTooLong,
+    HintLeaksWord,
+    WordTooShort,
+    WordTooLong,
+    WordInvalidCharacters,
+    WordNotInDictionary,
+    AttemptWrongLength,
+    WrongMatchMode,
+    InvalidLetter,
+    LetterAlreadyGuessed,
+    SoloUnavailable,
+    ChatEmpty,
+    ChatTooLong,
+    ChatRateLimited,
+    RecipientNotFound,
+    RoomNotFound,
+    RoomExists,
+    RoomNameInvalid,
+    RoomPasswordWrong,
+    PlayerInMatch,
+    TournamentInProgress,
+    TournamentTooLarge,
+    RateLimited,
+    LockedOut,
+    NotAdmin,
+    NotAuthorized,
+    AlreadyAuthorized,
+    Banned,
+    PlayerNotFound,
+    CannotKickSelf,
+}
+
+impl TryFrom<u8> for ErrorCode {
+    type Error = anyhow::Error;
+
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        match v {
+            x if x == ErrorCode::Unknown as u8 => Ok(ErrorCode::Unknown),
+            x if x == ErrorCode::HintLimitReached as u8 => Ok(ErrorCode::HintLimitReached),
+            x if x == ErrorCode::HintTooSoon as u8 => Ok(ErrorCode::HintTooSoon),
+            x if x == ErrorCode::HintTooLong as u8 => Ok(ErrorCode::HintTooLong),
+            x if x == ErrorCode::HintLeaksWord as u8 => Ok(ErrorCode::HintLeaksWord),
+            x if x == ErrorCode::WordTooShort as u8 => Ok(ErrorCode::WordTooShort),
+            x if x == ErrorCode::WordTooLong as u8 => Ok(ErrorCode::WordTooLong),
+            x if x == ErrorCode::WordInvalidCharacters as u8 => Ok(ErrorCode::WordInvalidCharacters),
+            x if x == ErrorCode::WordNotInDictionary as u8 => Ok(ErrorCode::WordNotInDictionary),
+            x if x == ErrorCode::AttemptWrongLength as u8 => Ok(ErrorCode::AttemptWrongLength),
+            x if x == ErrorCode::WrongMatchMode as u8 => Ok(ErrorCode::WrongMatchMode),
+            x if x == ErrorCode::InvalidLetter as u8 => Ok(ErrorCode::InvalidLetter),
+            x if x == ErrorCode::LetterAlreadyGuessed as u8 => Ok(ErrorCode::LetterAlreadyGuessed),
+            x if x == ErrorCode::SoloUnavailable as u8 => Ok(ErrorCode::SoloUnavailable),
+            x if x == ErrorCode::ChatEmpty as u8 => Ok(ErrorCode::ChatEmpty),
+            x if x == ErrorCode::ChatTooLong as u8 => Ok(ErrorCode::ChatTooLong),
+            x if x == ErrorCode::ChatRateLimited as u8 => Ok(ErrorCode::ChatRateLimited),
+            x if x == ErrorCode::RecipientNotFound as u8 => Ok(ErrorCode::RecipientNotFound),
+            x if x == ErrorCode::RoomNotFound as u8 => Ok(ErrorCode::RoomNotFound),
+            x if x == ErrorCode::RoomExists as u8 => Ok(ErrorCode::RoomExists),
+            x if x == ErrorCode::RoomNameInvalid as u8 => Ok(ErrorCode::RoomNameInvalid),
+            x if x == ErrorCode::RoomPasswordWrong as u8 => Ok(ErrorCode::RoomPasswordWrong),
+            x if x == ErrorCode::PlayerInMatch as u8 => Ok(ErrorCode::PlayerInMatch),
+            x if x == ErrorCode::TournamentInProgress as u8 => Ok(ErrorCode::TournamentInProgress),
+            x if x == ErrorCode::TournamentTooLarge as u8 => Ok(ErrorCode::TournamentTooLarge),
+            x if x == ErrorCode::RateLimited as u8 => Ok(ErrorCode::RateLimited),
+            x if x == ErrorCode::LockedOut as u8 => Ok(ErrorCode::LockedOut),
+            x if x == ErrorCode::NotAdmin as u8 => Ok(ErrorCode::NotAdmin),
+            x if x == ErrorCode::NotAuthorized as u8 => Ok(ErrorCode::NotAuthorized),
+            x if x == ErrorCode::AlreadyAuthorized as u8 => Ok(ErrorCode::AlreadyAuthorized),
+            x if x == ErrorCode::Banned as u8 => Ok(ErrorCode::Banned),
+            x if x == ErrorCode::PlayerNotFound as u8 => Ok(ErrorCode::PlayerNotFound),
+            x if x == ErrorCode::CannotKickSelf as u8 => Ok(ErrorCode::CannotKickSelf),
+            _ => bail!("Couldn't convert {} to ErrorCode", v),
+        }
+    }
+}
+
+/// Error with a code that is reported to the client, the server answers any other error with `ErrorCode::Unknown`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl GameError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> GameError {
+        GameError {
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// Extracts the code to report for any error.
+    pub fn code_of(err: &anyhow::Error) -> ErrorCode {
+        err.downcast_ref::<GameError>()
+            .map(|e| e.code)
+            .unwrap_or(ErrorCode::Unknown)
+    }
+}
+
+impl Display for GameError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({:?})", self.message, self.code)
+    }
+}
+
+impl std::error::Error for GameError {}
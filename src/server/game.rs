@@ -1,35 +1,189 @@
-use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
 use anyhow::bail;
-use crate::utils::ClientId;
+use crate::server::admin::MatchInfo;
+use crate::server::chat::{ChatMessage, ChatRules};
+use crate::server::config::{GameRules, HintRules};
+use crate::server::dictionary::Dictionary;
+use crate::server::error::{ErrorCode, GameError};
+use crate::server::hangman::{HangmanRules, HangmanState, LetterGuess};
+use crate::server::history::{MatchEvent, MatchEventKind, MatchHistory, MatchOutcome, MatchRecord};
+use crate::server::race::{Race, RaceResult, RaceStanding};
+use crate::server::ratings::{LeaderboardEntry, Ratings};
+use crate::server::scoring::{MatchScore, ScoringConfig};
+use crate::server::solo::{reveal_hint, SoloRules, WordPool, SERVER_ID};
+use crate::server::text::AnswerNormalization;
+use crate::server::tournament::{Leg, Tournament, TournamentFormat, TournamentPairing, TournamentRules, WordSource};
+use crate::server::wordle::{letter_feedback, AttemptFeedback};
+use crate::utils::{unix_millis, ClientId};
 
 pub struct Game {
     players: HashMap<ClientId, Player>,
     matches: Vec<Match>,
-    // WARN: should be a hash of the password
-    password: String,
+    races: Vec<Race>,
+    tournament: Option<Tournament>,
+    tournament_rules: TournamentRules,
+    history: MatchHistory,
+    // shared by all rooms
+    ratings: Arc<Mutex<Ratings>>,
+    scoring: ScoringConfig,
+    hint_rules: HintRules,
+    dictionary: Dictionary,
+    answers: AnswerNormalization,
+    hangman: HangmanRules,
+    solo: SoloRules,
+    word_pool: WordPool,
+    chat: ChatRules,
 }
 
+pub const MAX_NAME_LENGTH: usize = 32;
+
 pub struct Player {
     id: ClientId,
+    // empty for players that didn't pick an account name
+    name: String,
     state: PlayerState,
+    // when the player's recent chat messages were sent
+    chat_sent: VecDeque<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(u8)]
+pub enum MatchMode {
+    /// Attempts are only judged right or wrong.
+    Classic,
+    /// Attempts have to match the word's length and get per-letter feedback.
+    Wordle,
+    /// The guesser guesses single letters of a masked word until it's revealed or too many were wrong.
+    Hangman
+}
+
+impl TryFrom<u8> for MatchMode {
+    type Error = anyhow::Error;
+
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        match v {
+            x if x == MatchMode::Classic as u8 => Ok(MatchMode::Classic),
+            x if x == MatchMode::Wordle as u8 => Ok(MatchMode::Wordle),
+            x if x == MatchMode::Hangman as u8 => Ok(MatchMode::Hangman),
+            _ => bail!("Couldn't convert {} to MatchMode", v),
+        }
+    }
 }
 
 pub struct Match {
     challenger_id: ClientId,
     opponent_id: ClientId,
+    mode: MatchMode,
     word: String,
+    started_at: u64,
+    events: Vec<MatchEvent>,
+    // letters guessed in hangman matches
+    guessed: Vec<char>,
+    mistakes: u8,
+}
+
+impl Player {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn state(&self) -> PlayerState {
+        self.state
+    }
 }
 
 impl Match {
-    pub fn new(challenger_id: ClientId, opponent_id: ClientId, word: String) -> Self {
+    pub fn new(challenger_id: ClientId, opponent_id: ClientId, mode: MatchMode, word: String) -> Self {
         Self {
             challenger_id,
             opponent_id,
-            word
+            mode,
+            word,
+            started_at: unix_millis(),
+            events: Vec::new(),
+            guessed: Vec::new(),
+            mistakes: 0,
+        }
+    }
+
+    fn add_event(&mut self, kind: MatchEventKind, text: &str) {
+        self.events.push(MatchEvent {
+            kind,
+            text: text.to_string(),
+            timestamp: unix_millis(),
+        });
+    }
+
+    fn check_hint(&self, rules: &HintRules, answers: &AnswerNormalization, hint: &str) -> anyhow::Result<()> {
+        if hint.chars().count() > rules.max_length {
+            bail!(GameError::new(
+                ErrorCode::HintTooLong,
+                format!("Hint is longer than {} characters", rules.max_length)));
+        }
+
+        if rules.leak_check.leaks_word(answers, &self.word, hint) {
+            bail!(GameError::new(ErrorCode::HintLeaksWord, "Hint gives away the word"));
+        }
+
+        let hints: Vec<&MatchEvent> = self.events.iter().filter(|e| e.kind == MatchEventKind::Hint).collect();
+        if hints.len() >= rules.max_hints {
+            bail!(GameError::new(
+                ErrorCode::HintLimitReached,
+                format!("No more than {} hints per match", rules.max_hints)));
+        }
+
+        if let Some(last) = hints.last() {
+            let elapsed = unix_millis().saturating_sub(last.timestamp);
+            if elapsed < rules.min_interval.as_millis() as u64 {
+                bail!(GameError::new(
+                    ErrorCode::HintTooSoon,
+                    format!("Hints have to be {}ms apart", rules.min_interval.as_millis())));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn into_record(self, players: &HashMap<ClientId, Player>, outcome: MatchOutcome) -> MatchRecord {
+        let name = |id| players.get(&id).map(|p: &Player| p.name.clone()).unwrap_or_default();
+
+        MatchRecord {
+            challenger_id: self.challenger_id,
+            challenger_name: name(self.challenger_id),
+            opponent_id: self.opponent_id,
+            opponent_name: name(self.opponent_id),
+            word: self.word,
+            started_at: self.started_at,
+            ended_at: unix_millis(),
+            outcome,
+            events: self.events,
         }
     }
 }
 
+/// A match that just ended, the players still have to be notified.
+pub struct FinishedMatch {
+    pub challenger_id: ClientId,
+    pub opponent_id: ClientId,
+    pub score: MatchScore,
+    /// Set when this was the last match of a race.
+    pub race: Option<RaceResult>,
+}
+
+/// Changes to the room's tournament the participants have to be told about.
+pub struct TournamentUpdate {
+    pub participants: Vec<ClientId>,
+    pub pairings: Vec<TournamentPairing>,
+    /// Guessers whose matches began, with the masked word of hangman matches.
+    pub started: Vec<(ClientId, Option<HangmanState>)>,
+    /// Challengers that have to send a word, with the guesser it's for.
+    pub word_requests: Vec<(ClientId, ClientId)>,
+    /// Set once the tournament is over, `SERVER_ID` when every player left.
+    pub winner: Option<ClientId>,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PlayerState {
     Available,
@@ -37,30 +191,124 @@ pub enum PlayerState {
 }
 
 impl Game {
-    pub fn new(password: &str) -> Game {
-        Game {
+    pub fn new(rules: &GameRules, history: MatchHistory, ratings: Arc<Mutex<Ratings>>) -> anyhow::Result<Game> {
+        Ok(Game {
             players: HashMap::new(),
             matches: Vec::new(),
-            password: password.to_string(),
-        }
-    }
-
-    pub fn authorize(&self, password: &str) -> bool {
-        self.password == password
+            races: Vec::new(),
+            tournament: None,
+            tournament_rules: rules.tournament.clone(),
+            history,
+            ratings,
+            scoring: rules.scoring.clone(),
+            hint_rules: rules.hints.clone(),
+            dictionary: Dictionary::load(&rules.words, &rules.answers)?,
+            answers: rules.answers.clone(),
+            hangman: rules.hangman.clone(),
+            solo: rules.solo.clone(),
+            word_pool: WordPool::load(&rules.solo, unix_millis())?,
+            chat: rules.chat.clone(),
+        })
     }
 
     pub fn add_player(&mut self, id: ClientId) {
-        self.players.insert(id, Player {
+        self.insert_player(Player {
             id,
+            name: String::new(),
             state: PlayerState::Available,
+            chat_sent: VecDeque::new(),
         });
     }
 
-    pub fn opponents_id(&mut self, challenger_id: ClientId) -> anyhow::Result<ClientId> {
-        if let Some(m) = self.matches.iter().find(|x| x.challenger_id == challenger_id) {
-            return Ok(m.opponent_id)
+    pub fn insert_player(&mut self, player: Player) {
+        self.players.insert(player.id, player);
+    }
+
+    /// Removes a player that isn't in a match or a tournament so it can move to another room.
+    pub fn take_player(&mut self, id: ClientId) -> anyhow::Result<Player> {
+        if self.players.get(&id).is_some_and(|p| p.state != PlayerState::Available) {
+            bail!(GameError::new(ErrorCode::PlayerInMatch, "Can't leave the room during a match"));
+        }
+
+        if self.tournament.as_ref().is_some_and(|t| t.is_competing(id)) {
+            bail!(GameError::new(ErrorCode::TournamentInProgress, "Can't leave the room during a tournament"));
+        }
+
+        match self.players.remove(&id) {
+            Some(player) => Ok(player),
+            None => bail!("Player {} not found.", id)
+        }
+    }
+
+    pub fn set_player_name(&mut self, id: ClientId, name: &str) -> bool {
+        match self.players.get_mut(&id) {
+            Some(player) => {
+                player.name = name.to_string();
+                true
+            }
+            None => false
+        }
+    }
+
+    /// Whether a player other than `id` uses the name.
+    pub fn is_name_taken(&self, id: ClientId, name: &str) -> bool {
+        self.players.values().any(|p| p.id != id && p.name == name)
+    }
+
+    pub fn player_count(&self) -> usize {
+        self.players.len()
+    }
+
+    pub fn player_ids(&self) -> Vec<ClientId> {
+        self.players.keys().copied().collect()
+    }
+
+    pub fn player(&self, id: ClientId) -> Option<&Player> {
+        self.players.get(&id)
+    }
+
+    /// Matches being played, for admins.
+    pub fn match_infos(&self, room: &str) -> Vec<MatchInfo> {
+        self.matches
+            .iter()
+            .map(|m| MatchInfo {
+                room: room.to_string(),
+                challenger_id: m.challenger_id,
+                opponent_id: m.opponent_id,
+                mode: m.mode,
+                started_at: m.started_at,
+            })
+            .collect()
+    }
+
+    /// Checks the sender's limits and builds the message pushed to the recipients.
+    pub fn chat_message(&mut self, sender_id: ClientId, text: &str, direct: bool) -> anyhow::Result<ChatMessage> {
+        let Some(sender) = self.players.get_mut(&sender_id) else {
+            bail!("Player {} not found.", sender_id);
+        };
+
+        self.chat.check(&mut sender.chat_sent, text)?;
+
+        Ok(ChatMessage {
+            sender_id,
+            sender_name: sender.name.clone(),
+            direct,
+            text: text.to_string(),
+        })
+    }
+
+    /// Guessers of the challenger's match, a race has several of them.
+    pub fn opponent_ids(&mut self, challenger_id: ClientId) -> anyhow::Result<Vec<ClientId>> {
+        let ids: Vec<ClientId> = self.matches
+            .iter()
+            .filter(|x| x.challenger_id == challenger_id)
+            .map(|x| x.opponent_id)
+            .collect();
+
+        if ids.is_empty() {
+            bail!("Match not found with challenger id: {}", challenger_id)
         }
-        bail!("Match not found with challenger id: {}", challenger_id)
+        Ok(ids)
     }
 
     pub fn challenger_id(&mut self, opponent_id: ClientId) -> anyhow::Result<ClientId> {
@@ -70,34 +318,437 @@ impl Game {
         bail!("Match not found with opponent id: {}", opponent_id)
     }
 
-    pub fn validate_word(&mut self, opponent_id: ClientId, word: &str) -> anyhow::Result<bool> {
-        if let Some(m) = self.matches.iter().find(|x| x.opponent_id == opponent_id) {
-            return Ok(word.eq_ignore_ascii_case(&m.word));
+    /// Checks the hint against the rules and records it in every match of the challenger.
+    pub fn record_hint(&mut self, challenger_id: ClientId, hint: &str) -> anyhow::Result<()> {
+        let mut matches: Vec<&mut Match> = self.matches.iter_mut().filter(|x| x.challenger_id == challenger_id).collect();
+        if matches.is_empty() {
+            bail!("Match not found with challenger id: {}", challenger_id)
+        }
+
+        for m in matches.iter() {
+            m.check_hint(&self.hint_rules, &self.answers, hint)?;
+        }
+        for m in matches.iter_mut() {
+            m.add_event(MatchEventKind::Hint, hint);
+        }
+        Ok(())
+    }
+
+    pub fn validate_word(&mut self, opponent_id: ClientId, word: &str) -> anyhow::Result<AttemptFeedback> {
+        if let Some(m) = self.matches.iter_mut().find(|x| x.opponent_id == opponent_id) {
+            let secret = self.answers.apply(&m.word);
+            let attempt = self.answers.apply(word);
+
+            let letters = match m.mode {
+                MatchMode::Classic | MatchMode::Hangman => Vec::new(),
+                MatchMode::Wordle => {
+                    let length = secret.chars().count();
+                    if attempt.chars().count() != length {
+                        bail!(GameError::new(
+                            ErrorCode::AttemptWrongLength,
+                            format!("Attempt has to be {} letters long", length)));
+                    }
+                    letter_feedback(&secret, &attempt)
+                }
+            };
+
+            m.add_event(MatchEventKind::Attempt, word);
+            return Ok(AttemptFeedback {
+                correct: secret == attempt,
+                letters,
+            });
         }
         bail!("Match not found with opponent id: {}", opponent_id)
     }
 
-    pub fn remove_player(&mut self, id: ClientId) {
+    pub fn guess_letter(&mut self, opponent_id: ClientId, letter: &str) -> anyhow::Result<LetterGuess> {
+        let Some(m) = self.matches.iter_mut().find(|x| x.opponent_id == opponent_id) else {
+            bail!("Match not found with opponent id: {}", opponent_id)
+        };
+
+        if m.mode != MatchMode::Hangman {
+            bail!(GameError::new(ErrorCode::WrongMatchMode, "Letters can only be guessed in hangman matches"));
+        }
+
+        let normalized = self.answers.apply(letter);
+        let mut chars = normalized.chars();
+        let letter = match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_alphanumeric() => c,
+            _ => bail!(GameError::new(ErrorCode::InvalidLetter, format!("'{}' isn't a single letter", letter)))
+        };
+
+        if m.guessed.contains(&letter) {
+            bail!(GameError::new(ErrorCode::LetterAlreadyGuessed, format!("'{}' was already guessed", letter)));
+        }
+
+        let secret = self.answers.apply(&m.word);
+        let hit = secret.contains(letter);
+        m.guessed.push(letter);
+        if !hit {
+            m.mistakes += 1;
+        }
+        m.add_event(MatchEventKind::Letter, &letter.to_string());
+
+        Ok(LetterGuess {
+            hit,
+            state: HangmanState::new(&m.word, &self.answers, &m.guessed, m.mistakes, self.hangman.max_mistakes),
+        })
+    }
+
+    /// Initial masked word of a hangman match, `None` for other modes.
+    pub fn hangman_state(&self, opponent_id: ClientId) -> Option<HangmanState> {
+        self.matches
+            .iter()
+            .find(|x| x.opponent_id == opponent_id && x.mode == MatchMode::Hangman)
+            .map(|m| HangmanState::new(&m.word, &self.answers, &m.guessed, m.mistakes, self.hangman.max_mistakes))
+    }
+
+    /// Ends the match the opponent is guessing in. Solving a race's word also ends the matches
+    /// of the other guessers.
+    pub fn end_match(&mut self, opponent_id: ClientId, outcome: MatchOutcome) -> anyhow::Result<Vec<FinishedMatch>> {
+        let finished = self.finish_match(opponent_id, outcome)?;
+        let challenger_id = finished.challenger_id;
+        let mut result = vec![finished];
+
+        if outcome == MatchOutcome::Solved && self.races.iter().any(|r| r.challenger_id == challenger_id) {
+            for id in self.opponent_ids(challenger_id).unwrap_or_default() {
+                result.push(self.finish_match(id, MatchOutcome::Outraced)?);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Removes a single match, frees its players, updates their ratings and records it in the history.
+    fn finish_match(&mut self, opponent_id: ClientId, outcome: MatchOutcome) -> anyhow::Result<FinishedMatch> {
+        let Some(index) = self.matches.iter().position(|x| x.opponent_id == opponent_id) else {
+            bail!("Match not found with opponent id: {}", opponent_id)
+        };
+
+        let m = self.matches.remove(index);
+        let challenger_id = m.challenger_id;
+        let last = !self.matches.iter().any(|x| x.challenger_id == challenger_id);
+        if last {
+            self.mark_player_available(challenger_id);
+        }
+        self.mark_player_available(m.opponent_id);
+
+        let record = m.into_record(&self.players, outcome);
+
+        // the match is over either way, failing to persist it shouldn't fail the players' requests
+        if let Err(err) = self.ratings().and_then(|mut ratings| ratings.update(&record)) {
+            eprintln!("Couldn't update ratings: {}", err);
+        }
+        if let Err(err) = self.history.append(&record) {
+            eprintln!("Couldn't append to the match history: {}", err);
+        }
+        let score = self.scoring.score(&record, &self.answers);
+
+        if let Some(tournament) = &mut self.tournament {
+            tournament.record_leg(challenger_id, record.opponent_id, score.guesser);
+        }
+
+        let mut race = None;
+        if let Some(index) = self.races.iter().position(|r| r.challenger_id == challenger_id) {
+            self.races[index].add_standing(RaceStanding::new(&record, &score));
+            if last {
+                race = Some(self.races.remove(index).finish());
+            }
+        }
+
+        Ok(FinishedMatch {
+            challenger_id: record.challenger_id,
+            opponent_id: record.opponent_id,
+            score,
+            race,
+        })
+    }
+
+    pub fn remove_player(&mut self, id: ClientId) -> anyhow::Result<Vec<FinishedMatch>> {
+        // end the matches where the client is in, before the player's name is gone
+        let ended: Vec<(ClientId, MatchOutcome)> = self.matches
+            .iter()
+            .filter_map(|m| {
+                if m.challenger_id == id {
+                    Some((m.opponent_id, MatchOutcome::ChallengerLeft))
+                } else if m.opponent_id == id {
+                    Some((m.opponent_id, MatchOutcome::OpponentLeft))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        let mut finished = Vec::new();
+        for (opponent_id, outcome) in ended {
+            finished.append(&mut self.end_match(opponent_id, outcome)?);
+        }
+
+        // players waiting for a word from the player or for the player's word are free again
+        if let Some(tournament) = &mut self.tournament {
+            for waiting in tournament.forfeit(id) {
+                self.mark_player_available(waiting);
+            }
+        }
+
         self.players.remove(&id);
+        self.history.forget_client(id)?;
+        Ok(finished)
+    }
+
+    pub fn match_history(&self, id: ClientId, limit: usize) -> anyhow::Result<Vec<MatchRecord>> {
+        let name = self.players.get(&id).map(|p| p.name.as_str()).unwrap_or_default();
+        self.history.recent(id, name, limit)
+    }
 
-        // remove the matches where the client is in
-        self.matches.retain(|m| m.opponent_id != id && m.challenger_id != id);
+    pub fn leaderboard(&self, count: usize) -> anyhow::Result<Vec<LeaderboardEntry>> {
+        Ok(self.ratings()?.top(count))
+    }
+
+    fn ratings(&self) -> anyhow::Result<MutexGuard<'_, Ratings>> {
+        match self.ratings.lock() {
+            Ok(ratings) => Ok(ratings),
+            Err(_) => bail!("Ratings are poisoned"),
+        }
     }
 
     pub fn begin_match(
         &mut self,
         challenger_id: ClientId,
         opponent_id: ClientId,
+        mode: MatchMode,
         word: &str) -> anyhow::Result<()> {
 
+        self.dictionary.validate(word)?;
+
+        if challenger_id == opponent_id {
+            bail!("Player {} can't challenge itself.", challenger_id);
+        }
+
+        // check both players before marking either of them
+        self.ensure_player_available(challenger_id)?;
+        self.ensure_player_available(opponent_id)?;
         self.mark_player_in_match(challenger_id)?;
         self.mark_player_in_match(opponent_id)?;
 
-        self.matches.push(Match::new(challenger_id, opponent_id, word.to_string()));
+        self.matches.push(Match::new(challenger_id, opponent_id, mode, word.to_string()));
+
+        Ok(())
+    }
+
+    /// Starts a race where several guessers try to solve the same word, each in a match of their own.
+    pub fn begin_race(
+        &mut self,
+        challenger_id: ClientId,
+        opponent_ids: &[ClientId],
+        mode: MatchMode,
+        word: &str) -> anyhow::Result<()> {
+
+        self.dictionary.validate(word)?;
+
+        if opponent_ids.is_empty() {
+            bail!("A race needs at least one guesser");
+        }
+
+        for (i, id) in opponent_ids.iter().enumerate() {
+            if *id == challenger_id || opponent_ids[..i].contains(id) {
+                bail!("Player {} can't join the race twice.", id);
+            }
+        }
+
+        // check every player before marking any of them
+        self.ensure_player_available(challenger_id)?;
+        for id in opponent_ids {
+            self.ensure_player_available(*id)?;
+        }
+
+        self.mark_player_in_match(challenger_id)?;
+        for id in opponent_ids {
+            self.mark_player_in_match(*id)?;
+            self.matches.push(Match::new(challenger_id, *id, mode, word.to_string()));
+        }
+        self.races.push(Race::new(challenger_id, opponent_ids.to_vec()));
+
+        Ok(())
+    }
+
+    pub fn begin_tournament(
+        &mut self,
+        organizer_id: ClientId,
+        format: TournamentFormat,
+        words: WordSource,
+        mode: MatchMode,
+        player_ids: &[ClientId]) -> anyhow::Result<()> {
+
+        if self.tournament.is_some() {
+            bail!(GameError::new(ErrorCode::TournamentInProgress, "The room already runs a tournament"));
+        }
+
+        if words == WordSource::Server && self.word_pool.is_empty() {
+            bail!(GameError::new(ErrorCode::SoloUnavailable, "Server has no words to pick from"));
+        }
+
+        for id in player_ids {
+            self.ensure_player_available(*id)?;
+        }
+
+        self.tournament = Some(Tournament::new(organizer_id, format, words, mode, player_ids)?);
+        Ok(())
+    }
+
+    /// Begins the tournament matches whose players are free and collects what changed since the last call.
+    pub fn advance_tournament(&mut self) -> anyhow::Result<Option<TournamentUpdate>> {
+        let Some(mut tournament) = self.tournament.take() else {
+            return Ok(None);
+        };
+
+        let players = &self.players;
+        let legs = tournament.schedule(|id| players.get(&id).is_some_and(|p| p.state == PlayerState::Available));
+
+        let mut update = TournamentUpdate {
+            participants: tournament.participants(),
+            pairings: Vec::new(),
+            started: Vec::new(),
+            word_requests: Vec::new(),
+            winner: None,
+        };
+        if let Err(err) = self.start_tournament_legs(&mut tournament, legs, &mut update) {
+            self.tournament = Some(tournament);
+            return Err(err);
+        }
+
+        let changed = tournament.take_changed();
+        if !changed && update.started.is_empty() && update.word_requests.is_empty() {
+            self.tournament = Some(tournament);
+            return Ok(None);
+        }
+
+        update.pairings = tournament.pairings().to_vec();
+        update.winner = tournament.winner();
+        if !tournament.is_finished() {
+            self.tournament = Some(tournament);
+        }
+        Ok(Some(update))
+    }
+
+    /// Begins the legs with server words right away, legs with player words wait for the challenger's word.
+    fn start_tournament_legs(
+        &mut self,
+        tournament: &mut Tournament,
+        legs: Vec<Leg>,
+        update: &mut TournamentUpdate) -> anyhow::Result<()> {
+
+        // check every player and pick the words before marking any of them, both players of a
+        // pairing guess the same word
+        let mut words: HashMap<usize, String> = HashMap::new();
+        for leg in &legs {
+            self.ensure_player_available(leg.guesser_id)?;
+
+            if leg.challenger_id == SERVER_ID {
+                if let Entry::Vacant(entry) = words.entry(leg.pairing) {
+                    let Some(word) = self.word_pool.pick(None) else {
+                        bail!(GameError::new(ErrorCode::SoloUnavailable, "Server has no words to pick from"));
+                    };
+                    entry.insert(word.to_string());
+                }
+            } else {
+                self.ensure_player_available(leg.challenger_id)?;
+            }
+        }
+
+        for leg in legs {
+            self.mark_player_in_match(leg.guesser_id)?;
+
+            if leg.challenger_id == SERVER_ID {
+                tournament.start_leg(leg.challenger_id, leg.guesser_id);
+                self.matches.push(Match::new(SERVER_ID, leg.guesser_id, tournament.mode, words[&leg.pairing].clone()));
+                update.started.push((leg.guesser_id, self.hangman_state(leg.guesser_id)));
+            } else {
+                self.mark_player_in_match(leg.challenger_id)?;
+                update.word_requests.push((leg.challenger_id, leg.guesser_id));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Forfeits the legs whose challengers didn't send a word in time and begins the matches that
+    /// can follow.
+    pub fn expire_tournament_words(&mut self) -> anyhow::Result<Option<TournamentUpdate>> {
+        let Some(tournament) = &mut self.tournament else {
+            return Ok(None);
+        };
+
+        let expired = tournament.expire_words(self.tournament_rules.word_timeout);
+        if expired.is_empty() {
+            return Ok(None);
+        }
+
+        for leg in expired {
+            self.mark_player_available(leg.challenger_id);
+            self.mark_player_available(leg.guesser_id);
+        }
+        self.advance_tournament()
+    }
+
+    /// Begins the tournament match the challenger was asked to send a word for, returns the guesser's id.
+    pub fn set_tournament_word(&mut self, challenger_id: ClientId, word: &str) -> anyhow::Result<ClientId> {
+        let Some(tournament) = &mut self.tournament else {
+            bail!("No tournament is running");
+        };
+
+        let Some(guesser_id) = tournament.awaiting_word(challenger_id).map(|l| l.guesser_id) else {
+            bail!("No tournament match is waiting for a word from player {}", challenger_id);
+        };
+
+        self.dictionary.validate(word)?;
+        tournament.start_leg(challenger_id, guesser_id);
+        self.matches.push(Match::new(challenger_id, guesser_id, tournament.mode, word.to_string()));
+
+        Ok(guesser_id)
+    }
+
+    /// Starts a match where the server picks the word and plays the challenger.
+    pub fn begin_solo_match(&mut self, opponent_id: ClientId, mode: MatchMode, seed: Option<u64>) -> anyhow::Result<()> {
+        let Some(word) = self.word_pool.pick(seed).map(str::to_string) else {
+            bail!(GameError::new(ErrorCode::SoloUnavailable, "Server has no words to pick from"));
+        };
+
+        self.mark_player_in_match(opponent_id)?;
+        self.matches.push(Match::new(SERVER_ID, opponent_id, mode, word));
 
         Ok(())
     }
 
+    /// Hint the server sends in solo matches once enough attempts failed.
+    pub fn automatic_hint(&mut self, opponent_id: ClientId) -> Option<String> {
+        let m = self.matches
+            .iter_mut()
+            .find(|x| x.opponent_id == opponent_id && x.challenger_id == SERVER_ID)?;
+
+        if self.solo.hint_after == 0 {
+            return None;
+        }
+
+        let count = |kind| m.events.iter().filter(|e| e.kind == kind).count();
+        let hints = count(MatchEventKind::Hint);
+        if count(MatchEventKind::Attempt) < (hints + 1) * self.solo.hint_after {
+            return None;
+        }
+
+        let hint = reveal_hint(&m.word, hints + 1)?;
+        m.add_event(MatchEventKind::Hint, &hint);
+        Some(hint)
+    }
+
+    fn ensure_player_available(&self, id: ClientId) -> anyhow::Result<()> {
+        match self.players.get(&id) {
+            Some(player) if player.state != PlayerState::Available => bail!("Player {} not available.", id),
+            Some(_) => Ok(()),
+            None => bail!("Player {} not found.", id)
+        }
+    }
+
     fn mark_player_in_match(&mut self, id: ClientId) -> anyhow::Result<()> {
         if let Some(player) = self.players.get_mut(&id) {
             if player.state != PlayerState::Available {
@@ -111,6 +762,12 @@ impl Game {
         Ok(())
     }
 
+    fn mark_player_available(&mut self, id: ClientId) {
+        if let Some(player) = self.players.get_mut(&id) {
+            player.state = PlayerState::Available;
+        }
+    }
+
 
     pub fn list_of_opponents(&self) -> Vec<ClientId> {
         self.players
@@ -0,0 +1,92 @@
+use crate::bin_reader::BinReader;
+use crate::bin_writer::BinWriter;
+use crate::server::text::AnswerNormalization;
+
+pub const HIDDEN_LETTER: char = '_';
+
+#[derive(Debug, Clone)]
+pub struct HangmanRules {
+    /// Wrong letters the guesser can make before the match is lost.
+    pub max_mistakes: u8,
+}
+
+impl Default for HangmanRules {
+    fn default() -> Self {
+        HangmanRules { max_mistakes: 6 }
+    }
+}
+
+/// Progress of a `MatchMode::Hangman` match, letters that weren't guessed yet are shown as `HIDDEN_LETTER`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HangmanState {
+    pub mask: String,
+    pub mistakes: u8,
+    pub max_mistakes: u8,
+}
+
+impl HangmanState {
+    /// Masks the word as the challenger wrote it, a letter is revealed once every character it
+    /// normalizes to was guessed, e.g. "ß" after guessing "s".
+    pub fn new(word: &str, answers: &AnswerNormalization, guessed: &[char], mistakes: u8, max_mistakes: u8) -> HangmanState {
+        let mask = word
+            .chars()
+            .map(|c| {
+                let normalized = answers.apply(&c.to_string());
+                if !c.is_alphanumeric() || (!normalized.is_empty() && normalized.chars().all(|n| guessed.contains(&n))) {
+                    c
+                } else {
+                    HIDDEN_LETTER
+                }
+            })
+            .collect();
+
+        HangmanState {
+            mask,
+            mistakes,
+            max_mistakes,
+        }
+    }
+
+    pub fn is_solved(&self) -> bool {
+        !self.mask.contains(HIDDEN_LETTER)
+    }
+
+    pub fn is_lost(&self) -> bool {
+        self.mistakes >= self.max_mistakes
+    }
+
+    pub fn write(&self, bin_writer: &mut BinWriter) {
+        bin_writer.write_str(&self.mask);
+        bin_writer.write_u8(self.mistakes);
+        bin_writer.write_u8(self.max_mistakes);
+    }
+
+    pub fn read(bin_reader: &mut BinReader) -> anyhow::Result<HangmanState> {
+        Ok(HangmanState {
+            mask: bin_reader.read_str()?,
+            mistakes: bin_reader.read_u8()?,
+            max_mistakes: bin_reader.read_u8()?,
+        })
+    }
+}
+
+/// Result of a single letter guess.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LetterGuess {
+    pub hit: bool,
+    pub state: HangmanState,
+}
+
+impl LetterGuess {
+    pub fn write(&self, bin_writer: &mut BinWriter) {
+        bin_writer.write_u8(self.hit as u8);
+        self.state.write(bin_writer);
+    }
+
+    pub fn read(bin_reader: &mut BinReader) -> anyhow::Result<LetterGuess> {
+        Ok(LetterGuess {
+            hit: bin_reader.read_u8()? > 0,
+            state: HangmanState::read(bin_reader)?,
+        })
+    }
+}
@@ -0,0 +1,335 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+use anyhow::bail;
+use crate::bin_reader::BinReader;
+use crate::bin_writer::BinWriter;
+use crate::utils::ClientId;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(u8)]
+pub enum MatchOutcome {
+    Solved,
+    ChallengerLeft,
+    OpponentLeft,
+    /// The guesser ran out of mistakes in a `MatchMode::Hangman` match.
+    Failed,
+    /// Another guesser of the race solved the word first.
+    Outraced,
+    /// An admin ended the match.
+    Aborted
+}
+
+impl TryFrom<u8> for MatchOutcome {
+    type Error = anyhow::Error;
+
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        match v {
+            x if x == MatchOutcome::Solved as u8 => Ok(MatchOutcome::Solved),
+            x if x == MatchOutcome::ChallengerLeft as u8 => Ok(MatchOutcome::ChallengerLeft),
+            x if x == MatchOutcome::OpponentLeft as u8 => Ok(MatchOutcome::OpponentLeft),
+            x if x == MatchOutcome::Failed as u8 => Ok(MatchOutcome::Failed),
+            x if x == MatchOutcome::Outraced as u8 => Ok(MatchOutcome::Outraced),
+            x if x == MatchOutcome::Aborted as u8 => Ok(MatchOutcome::Aborted),
+            _ => bail!("Couldn't convert {} to MatchOutcome", v),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(u8)]
+pub enum MatchEventKind {
+    Attempt,
+    Hint,
+    Letter
+}
+
+impl TryFrom<u8> for MatchEventKind {
+    type Error = anyhow::Error;
+
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        match v {
+            x if x == MatchEventKind::Attempt as u8 => Ok(MatchEventKind::Attempt),
+            x if x == MatchEventKind::Hint as u8 => Ok(MatchEventKind::Hint),
+            x if x == MatchEventKind::Letter as u8 => Ok(MatchEventKind::Letter),
+            _ => bail!("Couldn't convert {} to MatchEventKind", v),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchEvent {
+    pub kind: MatchEventKind,
+    pub text: String,
+    pub timestamp: u64,
+}
+
+/// A finished match as it is stored in the history file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchRecord {
+    pub challenger_id: ClientId,
+    pub challenger_name: String,
+    pub opponent_id: ClientId,
+    pub opponent_name: String,
+    pub word: String,
+    pub started_at: u64,
+    pub ended_at: u64,
+    pub outcome: MatchOutcome,
+    pub events: Vec<MatchEvent>,
+}
+
+impl MatchRecord {
+    pub fn write(&self, bin_writer: &mut BinWriter) {
+        bin_writer.write_u32(self.challenger_id);
+        bin_writer.write_str(&self.challenger_name);
+        bin_writer.write_u32(self.opponent_id);
+        bin_writer.write_str(&self.opponent_name);
+        bin_writer.write_str(&self.word);
+        bin_writer.write_u64(self.started_at);
+        bin_writer.write_u64(self.ended_at);
+        bin_writer.write_u8(self.outcome as u8);
+        bin_writer.write_u16(self.events.len() as u16);
+        for event in &self.events {
+            bin_writer.write_u8(event.kind as u8);
+            bin_writer.write_u64(event.timestamp);
+            bin_writer.write_str(&event.text);
+        }
+    }
+
+    pub fn read(bin_reader: &mut BinReader) -> anyhow::Result<MatchRecord> {
+        let challenger_id = bin_reader.read_u32()?;
+        let challenger_name = bin_reader.read_str()?;
+        let opponent_id = bin_reader.read_u32()?;
+        let opponent_name = bin_reader.read_str()?;
+        let word = bin_reader.read_str()?;
+        let started_at = bin_reader.read_u64()?;
+        let ended_at = bin_reader.read_u64()?;
+        let outcome = MatchOutcome::try_from(bin_reader.read_u8()?)?;
+
+        let events_count = bin_reader.read_u16()? as usize;
+        let mut events = Vec::with_capacity(events_count);
+        for _ in 0..events_count {
+            events.push(MatchEvent {
+                kind: MatchEventKind::try_from(bin_reader.read_u8()?)?,
+                timestamp: bin_reader.read_u64()?,
+                text: bin_reader.read_str()?,
+            });
+        }
+
+        Ok(MatchRecord {
+            challenger_id,
+            challenger_name,
+            opponent_id,
+            opponent_name,
+            word,
+            started_at,
+            ended_at,
+            outcome,
+            events,
+        })
+    }
+
+    pub fn summary(&self) -> MatchSummary {
+        let count = |kind| self.events.iter().filter(|e| e.kind == kind).count() as u16;
+
+        MatchSummary {
+            challenger_id: self.challenger_id,
+            challenger_name: self.challenger_name.clone(),
+            opponent_id: self.opponent_id,
+            opponent_name: self.opponent_name.clone(),
+            word: self.word.clone(),
+            started_at: self.started_at,
+            ended_at: self.ended_at,
+            outcome: self.outcome,
+            attempts: count(MatchEventKind::Attempt),
+            hints: count(MatchEventKind::Hint),
+        }
+    }
+}
+
+/// Condensed form of a `MatchRecord` sent to clients querying their history.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchSummary {
+    pub challenger_id: ClientId,
+    pub challenger_name: String,
+    pub opponent_id: ClientId,
+    pub opponent_name: String,
+    pub word: String,
+    pub started_at: u64,
+    pub ended_at: u64,
+    pub outcome: MatchOutcome,
+    pub attempts: u16,
+    pub hints: u16,
+}
+
+impl MatchSummary {
+    pub fn encoded_len(&self) -> usize {
+        4 + 2 + self.challenger_name.len() + 4 + 2 + self.opponent_name.len() + 2 + self.word.len() + 8 + 8 + 1 + 2 + 2
+    }
+
+    pub fn write(&self, bin_writer: &mut BinWriter) {
+        bin_writer.write_u32(self.challenger_id);
+        bin_writer.write_str(&self.challenger_name);
+        bin_writer.write_u32(self.opponent_id);
+        bin_writer.write_str(&self.opponent_name);
+        bin_writer.write_str(&self.word);
+        bin_writer.write_u64(self.started_at);
+        bin_writer.write_u64(self.ended_at);
+        bin_writer.write_u8(self.outcome as u8);
+        bin_writer.write_u16(self.attempts);
+        bin_writer.write_u16(self.hints);
+    }
+
+    pub fn read(bin_reader: &mut BinReader) -> anyhow::Result<MatchSummary> {
+        Ok(MatchSummary {
+            challenger_id: bin_reader.read_u32()?,
+            challenger_name: bin_reader.read_str()?,
+            opponent_id: bin_reader.read_u32()?,
+            opponent_name: bin_reader.read_str()?,
+            word: bin_reader.read_str()?,
+            started_at: bin_reader.read_u64()?,
+            ended_at: bin_reader.read_u64()?,
+            outcome: MatchOutcome::try_from(bin_reader.read_u8()?)?,
+            attempts: bin_reader.read_u16()?,
+            hints: bin_reader.read_u16()?,
+        })
+    }
+}
+
+/// Most recent matches kept in memory for each player, more than a single query returns.
+const MAX_INDEXED_RECORDS: usize = 100;
+
+#[derive(Default)]
+struct HistoryIndex {
+    // named players, across sessions
+    names: HashMap<String, VecDeque<MatchRecord>>,
+    // unnamed players only for the current session, client ids restart with the server
+    clients: HashMap<ClientId, VecDeque<MatchRecord>>,
+}
+
+impl HistoryIndex {
+    /// Adds the record for both players, unnamed players of records loaded from disk are skipped.
+    fn insert(&mut self, record: &MatchRecord, loaded: bool) {
+        let players = [(record.challenger_id, &record.challenger_name), (record.opponent_id, &record.opponent_name)];
+        for (id, name) in players {
+            let records = if !name.is_empty() {
+                self.names.entry(name.clone()).or_default()
+            } else if !loaded {
+                self.clients.entry(id).or_default()
+            } else {
+                continue;
+            };
+
+            records.push_back(record.clone());
+            if records.len() > MAX_INDEXED_RECORDS {
+                records.pop_front();
+            }
+        }
+    }
+}
+
+/// Append-only log of finished matches. Every record is prefixed with its length as an u32.
+/// Recent matches of every player are indexed in memory, the file is only read on startup.
+#[derive(Clone)]
+pub struct MatchHistory {
+    path: Option<PathBuf>,
+    index: Arc<Mutex<HistoryIndex>>,
+}
+
+impl MatchHistory {
+    pub fn load(path: Option<PathBuf>) -> anyhow::Result<MatchHistory> {
+        let mut index = HistoryIndex::default();
+
+        if let Some(path) = &path {
+            match std::fs::read(path) {
+                Ok(data) => {
+                    for record in read_records(&data) {
+                        index.insert(&record, true);
+                    }
+                }
+                Err(err) if err.kind() == ErrorKind::NotFound => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(MatchHistory { path, index: Arc::new(Mutex::new(index)) })
+    }
+
+    pub fn append(&self, record: &MatchRecord) -> anyhow::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        self.index()?.insert(record, false);
+
+        let mut bin_writer = BinWriter::with_capacity(256);
+        bin_writer.write_u32(0);
+        record.write(&mut bin_writer);
+
+        let mut data = bin_writer.clone_data();
+        let len = (data.len() - 4) as u32;
+        data[..4].copy_from_slice(&len.to_le_bytes());
+
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        file.write_all(&data)?;
+
+        Ok(())
+    }
+
+    /// Returns up to `limit` of the most recent matches the client took part in, newest first.
+    /// Named players are matched by name across sessions, unnamed ones only by their client id.
+    pub fn recent(&self, client_id: ClientId, name: &str, limit: usize) -> anyhow::Result<Vec<MatchRecord>> {
+        let index = self.index()?;
+        let records = if name.is_empty() {
+            index.clients.get(&client_id)
+        } else {
+            index.names.get(name)
+        };
+
+        Ok(records
+            .map(|records| records.iter().rev().take(limit).cloned().collect())
+            .unwrap_or_default())
+    }
+
+    /// Drops the matches of an unnamed player that disconnected, nobody can query them anymore.
+    pub fn forget_client(&self, client_id: ClientId) -> anyhow::Result<()> {
+        self.index()?.clients.remove(&client_id);
+        Ok(())
+    }
+
+    fn index(&self) -> anyhow::Result<MutexGuard<'_, HistoryIndex>> {
+        match self.index.lock() {
+            Ok(index) => Ok(index),
+            Err(_) => bail!("History is poisoned"),
+        }
+    }
+}
+
+/// Reads every record of a history file, records that can't be read are skipped.
+fn read_records(data: &[u8]) -> Vec<MatchRecord> {
+    let mut records = Vec::new();
+    let mut bin_reader = BinReader::from_slice(data);
+    while bin_reader.remaining() >= 4 {
+        let Ok(len) = bin_reader.read_u32().map(|len| len as usize) else {
+            break;
+        };
+        if bin_reader.remaining() < len {
+            // partially written record at the end of the file
+            break;
+        }
+
+        let start = data.len() - bin_reader.remaining();
+        let mut record_reader = BinReader::from_slice(&data[start..start + len]);
+        match MatchRecord::read(&mut record_reader) {
+            Ok(record) => records.push(record),
+            Err(err) => eprintln!("Skipping corrupt history record at byte {}: {}", start, err),
+        }
+
+        if bin_reader.skip(len).is_err() {
+            break;
+        }
+    }
+    records
+}
@@ -0,0 +1,88 @@
+use crate::server::text::AnswerNormalization;
+
+/// How hard the server looks for the secret word inside hints.
+#[derive(Debug, Clone)]
+pub struct LeakCheck {
+    /// Reject hints containing the word anywhere, e.g. "maybe try 'test'".
+    pub substring: bool,
+    /// Reject hints with a word this close to the secret, e.g. "tesst". 0 disables the check.
+    pub max_edit_distance: usize,
+    /// Reject hints spelling the word out letter by letter, e.g. "t e s t" or "t-e-s-t".
+    pub spaced_letters: bool,
+}
+
+impl Default for LeakCheck {
+    fn default() -> Self {
+        LeakCheck {
+            substring: true,
+            max_edit_distance: 1,
+            spaced_letters: true,
+        }
+    }
+}
+
+impl LeakCheck {
+    /// Compares both through `answers`, so a hint is a leak whenever it contains an accepted answer.
+    pub fn leaks_word(&self, answers: &AnswerNormalization, word: &str, hint: &str) -> bool {
+        let word = answers.apply(word);
+        let hint = answers.apply(hint);
+        if word.is_empty() {
+            return false;
+        }
+
+        if self.substring && hint.contains(&word) {
+            return true;
+        }
+
+        let tokens: Vec<&str> = hint
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|t| !t.is_empty())
+            .collect();
+
+        // short words would be matched by almost any token
+        let word_chars: Vec<char> = word.chars().collect();
+        if self.max_edit_distance > 0 && word_chars.len() > 2 * self.max_edit_distance {
+            let close = tokens.iter().any(|t| {
+                let token: Vec<char> = t.chars().collect();
+                edit_distance(&token, &word_chars) <= self.max_edit_distance
+            });
+            if close {
+                return true;
+            }
+        }
+
+        if self.spaced_letters {
+            // join runs of single letter tokens back together
+            let mut run = String::new();
+            for token in tokens {
+                if token.chars().count() == 1 {
+                    run.push_str(token);
+                    if run.contains(&word) {
+                        return true;
+                    }
+                } else {
+                    run.clear();
+                }
+            }
+        }
+
+        false
+    }
+}
+
+/// Levenshtein distance between two strings.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == cb { 0 } else { 1 };
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use anyhow::bail;
+use crate::server::error::{ErrorCode, GameError};
+use crate::utils::ClientId;
+
+/// Token bucket holding up to `burst` tokens, refilled at `per_second`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub burst: u32,
+    pub per_second: u32,
+}
+
+/// Limits applied before a connection is accepted or a message is processed, `None` disables a limit.
+/// Connections without an IP, like Unix sockets, only get the per-connection limits.
+#[derive(Debug, Clone)]
+pub struct RateLimits {
+    /// Messages of a single connection.
+    pub messages: Option<RateLimit>,
+    /// Messages of all connections from the same IP together.
+    pub ip_messages: Option<RateLimit>,
+    /// New connections from the same IP.
+    pub ip_connections: Option<RateLimit>,
+    /// Connections the same IP can have open at once.
+    pub max_ip_connections: Option<usize>,
+    /// A connection is dropped after this many throttled messages in a row, it's only throttled when `None`.
+    pub max_violations: Option<u32>,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        RateLimits {
+            messages: Some(RateLimit { burst: 50, per_second: 20 }),
+            ip_messages: Some(RateLimit { burst: 200, per_second: 100 }),
+            ip_connections: Some(RateLimit { burst: 20, per_second: 5 }),
+            max_ip_connections: Some(32),
+            max_violations: Some(20),
+        }
+    }
+}
+
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: &RateLimit) -> TokenBucket {
+        TokenBucket {
+            tokens: limit.burst as f64,
+            updated: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, limit: &RateLimit) {
+        let now = Instant::now();
+        let refill = now.duration_since(self.updated).as_secs_f64() * limit.per_second as f64;
+        self.tokens = (self.tokens + refill).min(limit.burst as f64);
+        self.updated = now;
+    }
+
+    fn is_full(&mut self, limit: &RateLimit) -> bool {
+        self.refill(limit);
+        self.tokens >= limit.burst as f64
+    }
+
+    fn try_take(&mut self, limit: &RateLimit) -> bool {
+        self.refill(limit);
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+/// Connections open per IP, shared with the slots that release them.
+type OpenConnections = Arc<Mutex<HashMap<IpAddr, usize>>>;
+
+/// An open connection of an IP, counted until the slot is dropped.
+struct ConnectionSlot {
+    ip: IpAddr,
+    open: OpenConnections,
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        let Ok(mut open) = self.open.lock() else {
+            return;
+        };
+
+        if let Some(count) = open.get_mut(&self.ip) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                open.remove(&self.ip);
+            }
+        }
+    }
+}
+
+/// Bucket of a single connection, checked by its connection task before a message is handed to
+/// the server loop. Holds the connection's slot of its IP, dropping it releases the slot however
+/// the connection ended.
+pub struct MessageThrottle {
+    limit: Option<RateLimit>,
+    bucket: Option<TokenBucket>,
+    max_violations: Option<u32>,
+    // throttled messages in a row
+    violations: u32,
+    _slot: Option<ConnectionSlot>,
+}
+
+impl MessageThrottle {
+    fn new(limits: &RateLimits, slot: Option<ConnectionSlot>) -> MessageThrottle {
+        MessageThrottle {
+            limit: limits.messages,
+            bucket: limits.messages.as_ref().map(TokenBucket::new),
+            max_violations: limits.max_violations,
+            violations: 0,
+            _slot: slot,
+        }
+    }
+
+    pub fn check_message(&mut self) -> anyhow::Result<()> {
+        let allowed = match (&mut self.bucket, &self.limit) {
+            (Some(bucket), Some(limit)) => bucket.try_take(limit),
+            _ => true,
+        };
+
+        if !allowed {
+            self.violations += 1;
+            bail!(GameError::new(ErrorCode::RateLimited, "Too many messages, slow down"));
+        }
+
+        self.violations = 0;
+        Ok(())
+    }
+
+    /// Whether the connection kept sending after being throttled and should be dropped.
+    pub fn should_disconnect(&self) -> bool {
+        self.max_violations.is_some_and(|max| self.violations >= max)
+    }
+}
+
+struct ConnectionState {
+    ip: Option<IpAddr>,
+    // messages in a row throttled by the IP's bucket
+    violations: u32,
+}
+
+struct IpState {
+    messages: Option<TokenBucket>,
+    connections: Option<TokenBucket>,
+}
+
+/// Tracks the connections and buckets of every source IP, the bucket of a single connection is a
+/// `MessageThrottle`.
+pub struct RateLimiter {
+    limits: RateLimits,
+    connections: HashMap<ClientId, ConnectionState>,
+    ips: HashMap<IpAddr, IpState>,
+    open: OpenConnections,
+}
+
+impl RateLimiter {
+    pub fn new(limits: RateLimits) -> RateLimiter {
+        RateLimiter {
+            limits,
+            connections: HashMap::new(),
+            ips: HashMap::new(),
+            open: OpenConnections::default(),
+        }
+    }
+
+    /// Registers a new connection, fails when its IP connects too often or has too many connections open.
+    /// The returned throttle holds the connection's slot of its IP.
+    pub fn accept(&mut self, client_id: ClientId, ip: Option<IpAddr>) -> anyhow::Result<MessageThrottle> {
+        let mut slot = None;
+
+        if let Some(ip) = ip {
+            if !self.ips.contains_key(&ip) {
+                self.prune();
+            }
+
+            let limits = &self.limits;
+            let state = self.ips.entry(ip).or_insert_with(|| IpState {
+                messages: limits.ip_messages.as_ref().map(TokenBucket::new),
+                connections: limits.ip_connections.as_ref().map(TokenBucket::new),
+            });
+
+            let mut open = match self.open.lock() {
+                Ok(open) => open,
+                Err(_) => bail!("Open connections are poisoned"),
+            };
+            let count = open.entry(ip).or_default();
+            if limits.max_ip_connections.is_some_and(|max| *count >= max) {
+                bail!("Too many connections from {}", ip);
+            }
+            if let (Some(bucket), Some(limit)) = (&mut state.connections, &limits.ip_connections) {
+                if !bucket.try_take(limit) {
+                    bail!("{} connects too often", ip);
+                }
+            }
+            *count += 1;
+            slot = Some(ConnectionSlot { ip, open: self.open.clone() });
+        }
+
+        self.connections.insert(client_id, ConnectionState { ip, violations: 0 });
+        Ok(MessageThrottle::new(&self.limits, slot))
+    }
+
+    /// Takes a token for a message of the connection from the bucket of its IP.
+    pub fn check_message(&mut self, client_id: ClientId) -> anyhow::Result<()> {
+        let Some(connection) = self.connections.get_mut(&client_id) else {
+            return Ok(());
+        };
+
+        let allowed = match (connection.ip.and_then(|ip| self.ips.get_mut(&ip)), &self.limits.ip_messages) {
+            (Some(IpState { messages: Some(bucket), .. }), Some(limit)) => bucket.try_take(limit),
+            _ => true,
+        };
+
+        if !allowed {
+            connection.violations += 1;
+            bail!(GameError::new(ErrorCode::RateLimited, "Too many messages, slow down"));
+        }
+
+        connection.violations = 0;
+        Ok(())
+    }
+
+    /// Whether the connection kept sending after being throttled and should be dropped.
+    pub fn should_disconnect(&self, client_id: ClientId) -> bool {
+        match (self.connections.get(&client_id), self.limits.max_violations) {
+            (Some(connection), Some(max)) => connection.violations >= max,
+            _ => false,
+        }
+    }
+
+    /// Forgets the connection, its slot is released by its throttle.
+    pub fn remove(&mut self, client_id: ClientId) {
+        self.connections.remove(&client_id);
+    }
+
+    /// Forgets IPs without connections once their buckets refilled, until then reconnecting
+    /// doesn't reset them.
+    fn prune(&mut self) {
+        let limits = &self.limits;
+        let open = match self.open.lock() {
+            Ok(open) => open,
+            Err(_) => return,
+        };
+        self.ips.retain(|ip, state| {
+            let rested = |bucket: &mut Option<TokenBucket>, limit: &Option<RateLimit>| match (bucket, limit) {
+                (Some(bucket), Some(limit)) => bucket.is_full(limit),
+                _ => true,
+            };
+            open.contains_key(ip)
+                || !rested(&mut state.messages, &limits.ip_messages)
+                || !rested(&mut state.connections, &limits.ip_connections)
+        });
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use anyhow::bail;
+use crate::server::error::{ErrorCode, GameError};
+
+/// Locks an IP out of authorizing after repeated wrong passwords, every further failure doubles
+/// the lockout.
+#[derive(Debug, Clone)]
+pub struct LockoutRules {
+    /// Failed attempts before the first lockout, lockouts are disabled when `None`.
+    pub max_failures: Option<u32>,
+    pub lockout: Duration,
+    pub max_lockout: Duration,
+}
+
+impl Default for LockoutRules {
+    fn default() -> Self {
+        LockoutRules {
+            max_failures: Some(5),
+            lockout: Duration::from_secs(30),
+            max_lockout: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+struct FailedAttempts {
+    count: u32,
+    last: Instant,
+    locked_until: Option<Instant>,
+}
+
+/// Failed authorizations of every source IP, connections without an IP are never locked out.
+pub struct Lockouts {
+    rules: LockoutRules,
+    attempts: HashMap<IpAddr, FailedAttempts>,
+}
+
+impl Lockouts {
+    pub fn new(rules: LockoutRules) -> Lockouts {
+        Lockouts {
+            rules,
+            attempts: HashMap::new(),
+        }
+    }
+
+    /// Fails while the IP is locked out.
+    pub fn check(&self, ip: Option<IpAddr>) -> anyhow::Result<()> {
+        let locked_until = ip
+            .and_then(|ip| self.attempts.get(&ip))
+            .and_then(|attempts| attempts.locked_until);
+
+        if let Some(until) = locked_until {
+            let now = Instant::now();
+            if until > now {
+                bail!(GameError::new(
+                    ErrorCode::LockedOut,
+                    format!("Too many failed authorizations, try again in {}s", (until - now).as_secs() + 1)));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn record_failure(&mut self, ip: Option<IpAddr>) {
+        let Some(ip) = ip else {
+            return;
+        };
+
+        if !self.attempts.contains_key(&ip) {
+            self.prune();
+        }
+
+        let now = Instant::now();
+        let attempts = self.attempts.entry(ip).or_insert(FailedAttempts { count: 0, last: now, locked_until: None });
+        attempts.count += 1;
+        attempts.last = now;
+
+        let Some(max) = self.rules.max_failures else {
+            return;
+        };
+        if attempts.count >= max {
+            let doublings = (attempts.count - max).min(31);
+            let lockout = self.rules.lockout.saturating_mul(1 << doublings).min(self.rules.max_lockout);
+            attempts.locked_until = Some(now + lockout);
+
+            eprintln!("Locked out {} for {}s after {} failed authorizations", ip, lockout.as_secs(), attempts.count);
+        }
+    }
+
+    pub fn record_success(&mut self, ip: Option<IpAddr>) {
+        if let Some(ip) = ip {
+            self.attempts.remove(&ip);
+        }
+    }
+
+    /// Forgets the failed attempts of the IP, returns whether there were any.
+    pub fn clear(&mut self, ip: IpAddr) -> bool {
+        self.attempts.remove(&ip).is_some()
+    }
+
+    /// Forgets IPs that haven't failed for longer than the longest lockout, until then a single
+    /// failure locks them out again.
+    fn prune(&mut self) {
+        let max_lockout = self.rules.max_lockout;
+        self.attempts.retain(|_, attempts| attempts.last.elapsed() < max_lockout);
+    }
+}
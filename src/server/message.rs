@@ -11,7 +11,21 @@ pub enum ServerMessage {
     Hint,
     ListOfOpponents,
     Attempt,
-    AttemptResult
+    AttemptResult,
+    MatchHistory,
+    Leaderboard,
+    MatchEnded,
+    HangmanState,
+    LetterResult,
+    LetterGuessed,
+    Chat,
+    ListOfRooms,
+    RaceStandings,
+    TournamentBracket,
+    TournamentWordRequest,
+    TournamentWinner,
+    ListOfConnections,
+    ListOfMatches
 }
 
 impl TryFrom<u8> for ServerMessage {
@@ -28,6 +42,20 @@ impl TryFrom<u8> for ServerMessage {
             x if x == ServerMessage::Hint as u8 => Ok(ServerMessage::Hint),
             x if x == ServerMessage::Attempt as u8 => Ok(ServerMessage::Attempt),
             x if x == ServerMessage::AttemptResult as u8 => Ok(ServerMessage::AttemptResult),
+            x if x == ServerMessage::MatchHistory as u8 => Ok(ServerMessage::MatchHistory),
+            x if x == ServerMessage::Leaderboard as u8 => Ok(ServerMessage::Leaderboard),
+            x if x == ServerMessage::MatchEnded as u8 => Ok(ServerMessage::MatchEnded),
+            x if x == ServerMessage::HangmanState as u8 => Ok(ServerMessage::HangmanState),
+            x if x == ServerMessage::LetterResult as u8 => Ok(ServerMessage::LetterResult),
+            x if x == ServerMessage::LetterGuessed as u8 => Ok(ServerMessage::LetterGuessed),
+            x if x == ServerMessage::Chat as u8 => Ok(ServerMessage::Chat),
+            x if x == ServerMessage::ListOfRooms as u8 => Ok(ServerMessage::ListOfRooms),
+            x if x == ServerMessage::RaceStandings as u8 => Ok(ServerMessage::RaceStandings),
+            x if x == ServerMessage::TournamentBracket as u8 => Ok(ServerMessage::TournamentBracket),
+            x if x == ServerMessage::TournamentWordRequest as u8 => Ok(ServerMessage::TournamentWordRequest),
+            x if x == ServerMessage::TournamentWinner as u8 => Ok(ServerMessage::TournamentWinner),
+            x if x == ServerMessage::ListOfConnections as u8 => Ok(ServerMessage::ListOfConnections),
+            x if x == ServerMessage::ListOfMatches as u8 => Ok(ServerMessage::ListOfMatches),
             _ => bail!("Couldn't convert {} to ServerMessage", v),
         }
     }
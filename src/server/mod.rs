@@ -1,20 +1,77 @@
 use crate::bin_reader::BinReader;
 use crate::bin_writer::BinWriter;
+use crate::capture::{CaptureKind, CaptureWriter};
+use crate::json::{authorized_to_json, client_message_from_json, server_message_to_json, MAX_JSON_LINE_LENGTH};
 use crate::utils::{create_framed_stream, ClientId, MAX_PACKET_LENGTH};
 use futures::channel::mpsc::{UnboundedReceiver, UnboundedSender};
-use futures::{SinkExt, StreamExt};
+use futures::{future, Sink, SinkExt, Stream, StreamExt, TryStreamExt};
 use std::collections::HashMap;
+use std::net::IpAddr;
 use anyhow::bail;
-use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
-use tokio_util::bytes::Bytes;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::ToSocketAddrs;
+use tokio_rustls::TlsAcceptor;
+use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tokio_util::bytes::{Bytes, BytesMut};
+use tokio_util::codec::{Framed, LinesCodec};
 
+mod accounts;
+mod acceptor;
+mod admin;
+mod chat;
+mod config;
+mod dictionary;
+mod error;
 mod game;
+mod hangman;
+mod history;
+mod leak;
+mod limits;
+mod lockout;
 mod message;
+mod race;
+mod ratings;
+mod room;
+mod scoring;
+mod solo;
 mod tcp_client;
+mod text;
+mod tournament;
+mod wordle;
 
-use crate::server::game::Game;
+use crate::server::accounts::Accounts;
+use crate::server::admin::{Bans, Moderation};
+use crate::server::game::{FinishedMatch, Game, PlayerState, TournamentUpdate};
+use crate::server::limits::{MessageThrottle, RateLimiter};
+use crate::server::lockout::Lockouts;
+use crate::server::room::Rooms;
+pub use game::MatchMode;
 use crate::server::tcp_client::TcpClient;
+pub use acceptor::{Acceptor, Connection, MemoryAcceptor, MemoryConnector, TcpAcceptor, Transport};
+pub use admin::{Ban, ConnectionInfo, MatchInfo};
+#[cfg(unix)]
+pub use acceptor::UnixAcceptor;
+pub use chat::{ChatMessage, ChatRules};
+pub use config::{GameRules, HintRules, RoomConfig, ServerConfig};
+pub use dictionary::{CharacterSet, WordRules};
+pub use error::{ErrorCode, GameError};
+pub use hangman::{HangmanRules, HangmanState, LetterGuess, HIDDEN_LETTER};
+pub use history::{MatchOutcome, MatchSummary};
+pub use leak::LeakCheck;
+pub use limits::{RateLimit, RateLimits};
+pub use lockout::LockoutRules;
 pub use message::ServerMessage;
+pub use race::RaceStanding;
+pub use ratings::LeaderboardEntry;
+pub use room::{RoomInfo, LOBBY_ROOM};
+pub use scoring::{MatchScore, ScoringConfig};
+pub use solo::{SoloRules, SERVER_ID};
+pub(crate) use solo::reveal_hint;
+pub use text::{AnswerNormalization, NormalizationForm};
+pub use tournament::{PairingState, TournamentFormat, TournamentPairing, TournamentRules, WordSource, MAX_TOURNAMENT_PAIRINGS};
+pub use wordle::{AttemptFeedback, LetterFeedback};
+pub(crate) use wordle::{letter_feedback, read_letters, write_letters};
 use crate::client::ClientMessage;
 
 #[derive(Debug, Clone)]
@@ -23,60 +80,152 @@ pub enum TcpMessage {
     Disconnect(u32),
 }
 
+/// Account password checked off the server loop, sent back to it once hashing is done.
+struct AccountVerification {
+    client_id: ClientId,
+    name: String,
+    verified: bool,
+}
+
+/// Maximum number of matches returned for a single history query.
+const MAX_HISTORY_ENTRIES: usize = 20;
+
+/// Maximum number of players returned for a single leaderboard query.
+const MAX_LEADERBOARD_ENTRIES: usize = 20;
+
 pub async fn run_async<A: ToSocketAddrs>(addr: A, password: &str) -> anyhow::Result<()> {
+    run_with_config_async(addr, ServerConfig::new(password)).await
+}
+
+pub async fn run_with_config_async<A: ToSocketAddrs>(addr: A, config: ServerConfig) -> anyhow::Result<()> {
+    let mut acceptors: Vec<Box<dyn Acceptor>> = vec![Box::new(TcpAcceptor::bind(addr, Transport::Framed).await?)];
+    if let Some(addr) = config.websocket_addr {
+        acceptors.push(Box::new(TcpAcceptor::bind(addr, Transport::WebSocket).await?));
+    }
+    if let Some(addr) = config.json_addr {
+        acceptors.push(Box::new(TcpAcceptor::bind(addr, Transport::Json).await?));
+    }
+    #[cfg(unix)]
+    if let Some(path) = &config.unix_path {
+        acceptors.push(Box::new(UnixAcceptor::bind(path)?));
+    }
+
+    run_with_acceptor_async(acceptors, config).await
+}
+
+/// Runs the server on connections from any source, the additional listeners of `config` aren't
+/// bound here.
+pub async fn run_with_acceptor_async<A: Acceptor>(mut acceptor: A, config: ServerConfig) -> anyhow::Result<()> {
     let mut client_id_counter: u32 = 1;
     let mut clients: HashMap<u32, TcpClient> = HashMap::new();
-    let mut game = Game::new(password);
+    let mut rooms = Rooms::new(&config)?;
+    let tls = config.tls.as_ref().map(|tls| tls.acceptor()).transpose()?;
     let (in_sender, mut in_receiver) = futures::channel::mpsc::unbounded::<TcpMessage>();
-
-    let listener = TcpListener::bind(addr).await?;
+    let (verified_sender, mut verified_receiver) = futures::channel::mpsc::unbounded::<AccountVerification>();
+    let capture = config.capture_path.as_deref().map(CaptureWriter::create).transpose()?;
+    let mut limiter = RateLimiter::new(config.limits.clone());
+    let mut moderation = Moderation {
+        lockouts: Lockouts::new(config.lockout.clone()),
+        bans: Bans::default(),
+        admin_password: config.admin_password.clone(),
+    };
 
     let mut bin_writer = BinWriter::with_capacity(MAX_PACKET_LENGTH);
 
+    // tournament word timeouts are checked once a second
+    let mut tournament_timer = tokio::time::interval(std::time::Duration::from_secs(1));
+
     loop {
         tokio::select! {
-            // accept new TCP connections
-            Ok((stream, _)) = listener.accept() => {
+            // accept new connections
+            Ok(connection) = acceptor.accept_async() => {
 
                 // get new client id
                 let new_id = client_id_counter;
                 client_id_counter += 1;
 
+                // dropping the connection closes it
+                if moderation.bans.is_ip_banned(connection.peer_ip) {
+                    continue;
+                }
+                let throttle = match limiter.accept(new_id, connection.peer_ip) {
+                    Ok(throttle) => throttle,
+                    Err(err) => {
+                        eprintln!("Refused connection: {}", err);
+                        continue;
+                    }
+                };
+
                 // accept the client
                 match accept_client(
                     new_id,
-                    stream,
+                    connection,
+                    tls.clone(),
+                    capture.clone(),
+                    throttle,
                     in_sender.clone(),
                     &mut clients).await {
-                        Ok(()) => game.add_player(new_id),
-                        Err(err) => eprintln!("Couldn't accept client: {}", err)
+                        Ok(()) => rooms.add_player(new_id),
+                        Err(err) => {
+                            // the throttle was dropped with the connection, releasing its slot
+                            limiter.remove(new_id);
+                            eprintln!("Couldn't accept client: {}", err);
+                        }
                     }
             },
             Some(msg) = in_receiver.next() => {
                     if let TcpMessage::Message(client_id, ref msg) = msg {
-
                     let mut bin_reader = BinReader::from_bytes(msg);
 
-                    if let Err(err) = process_message(
-                        client_id,
-                        &mut game,
-                        &mut clients,
-                        &mut bin_reader,
-                        &mut bin_writer).await {
+                    // messages throttled by the IP's bucket are answered with an error without being processed
+                    let result = match limiter.check_message(client_id) {
+                        Ok(()) => process_message(
+                            client_id,
+                            &mut rooms,
+                            &mut clients,
+                            &mut moderation,
+                            &verified_sender,
+                            &mut bin_reader,
+                            &mut bin_writer).await,
+                        Err(err) => Err(err),
+                    };
+
+                    if let Err(err) = result {
 
                         eprintln!("Couldn't process message: {}", err);
 
-                        // on error send Err flag
+                        // on error send Err flag with the reason
                         if let Some(client) = clients.get_mut(&client_id) {
                             bin_writer.clear();
                             bin_writer.write_u8(ServerMessage::Err as u8);
-                            _ = client.send_async(&bin_writer);
+                            bin_writer.write_u8(GameError::code_of(&err) as u8);
+                            _ = client.send_async(&bin_writer).await;
+
+                            if limiter.should_disconnect(client_id) {
+                                eprintln!("Disconnecting client {} for ignoring rate limits", client_id);
+                                _ = client.disconnect_async().await;
+                            }
                         }
                     }
                 }
                  else if let TcpMessage::Disconnect(client_id) = msg {
-                    clients.remove(&client_id);
-                    game.remove_player(client_id);
+                    // kicked clients are removed before their connection task reports the disconnect
+                    limiter.remove(client_id);
+                    if let Err(err) = remove_client_async(client_id, &mut rooms, &mut clients, &mut bin_writer).await {
+                        eprintln!("Couldn't remove player: {}", err);
+                    }
+                }
+            },
+            Some(verification) = verified_receiver.next() => {
+                if let Err(err) = finish_authorization_async(verification, &mut rooms, &mut clients, &mut moderation, &mut bin_writer).await {
+                    eprintln!("Couldn't authorize client: {}", err);
+                }
+            },
+            _ = tournament_timer.tick() => {
+                for update in rooms.expire_tournament_words() {
+                    if let Err(err) = send_tournament_update_async(update, &mut clients, &mut bin_writer).await {
+                        eprintln!("Couldn't notify players: {}", err);
+                    }
                 }
             }
         }
@@ -85,26 +234,236 @@ pub async fn run_async<A: ToSocketAddrs>(addr: A, password: &str) -> anyhow::Res
 
 async fn process_message(
     client_id: ClientId,
-    game: &mut Game,
+    rooms: &mut Rooms,
     clients: &mut HashMap<u32, TcpClient>,
+    moderation: &mut Moderation,
+    verified_sender: &UnboundedSender<AccountVerification>,
     bin_reader: &mut BinReader<'_>,
     bin_writer: &mut BinWriter,
 ) -> anyhow::Result<()> {
     bin_writer.clear();
     let mut disconnect = false;
+    let peer_ip = clients.get(&client_id).and_then(|c| c.peer_ip);
+
+    let flag = ClientMessage::try_from(bin_reader.read_u8()?)?;
+    let authorized = clients.get(&client_id).is_some_and(|c| c.authorized);
+    if flag != ClientMessage::Authorization && !authorized {
+        bail!(GameError::new(ErrorCode::NotAuthorized, "Authorize first"));
+    }
+    // authorizing again would rename the player mid-session
+    if flag == ClientMessage::Authorization && authorized {
+        bail!(GameError::new(ErrorCode::AlreadyAuthorized, "Already authorized"));
+    }
+    if flag == ClientMessage::Authorization && clients.get(&client_id).is_some_and(|c| c.authorizing) {
+        bail!(GameError::new(ErrorCode::AlreadyAuthorized, "The account password is still being verified"));
+    }
 
-    let flag = ClientMessage::try_from(bin_reader.read_u8())?;
     match flag {
         ClientMessage::Authorization => {
-            let password = bin_reader.read_str();
+            let password = bin_reader.read_str()?;
+            let name = bin_reader.read_str()?;
+            let account_password = bin_reader.read_str()?;
+
+            let allowed = moderation.lockouts
+                .check(peer_ip)
+                .and_then(|_| moderation.bans.check(peer_ip, &name));
 
-            if game.authorize(&password) {
+            if let Err(err) = allowed {
+                // answered in place of the id, the connection is closed either way
+                bin_writer.write_u8(ServerMessage::Err as u8);
+                bin_writer.write_u8(GameError::code_of(&err) as u8);
+                disconnect = true;
+            } else if !rooms.check_authorization(client_id, &password, &name) {
+                moderation.lockouts.record_failure(peer_ip);
+                disconnect = true;
+            } else if !name.is_empty() {
+                // hashing would hold up every other connection, the id is sent once the
+                // verification comes back to the server loop
+                if let Some(client) = clients.get_mut(&client_id) {
+                    client.authorizing = true;
+                }
+                let accounts = rooms.accounts();
+                let verified_sender = verified_sender.clone();
+                tokio::spawn(async move {
+                    let verified = Accounts::verify_or_register_async(accounts, name.clone(), account_password).await;
+                    _ = verified_sender.unbounded_send(AccountVerification { client_id, name, verified });
+                });
+                return Ok(());
+            } else if rooms.set_player_name(client_id, &name) {
+                moderation.lockouts.record_success(peer_ip);
+                if let Some(client) = clients.get_mut(&client_id) {
+                    client.authorized = true;
+                }
                 bin_writer.write_u32(client_id)
             } else {
+                moderation.lockouts.record_failure(peer_ip);
                 disconnect = true;
             }
         }
+        ClientMessage::AdminAuthorization => {
+            let password = bin_reader.read_str()?;
+
+            moderation.lockouts.check(peer_ip)?;
+            if moderation.admin_password.as_ref().is_none_or(|admin_password| *admin_password != password) {
+                moderation.lockouts.record_failure(peer_ip);
+                bail!(GameError::new(ErrorCode::NotAdmin, "Wrong admin password"));
+            }
+
+            moderation.lockouts.record_success(peer_ip);
+            if let Some(client) = clients.get_mut(&client_id) {
+                client.admin = true;
+            }
+            bin_writer.write_u8(ServerMessage::Ok as u8);
+        }
+        ClientMessage::ClearLockout => {
+            require_admin(client_id, clients)?;
+            let ip: IpAddr = bin_reader.read_str()?.parse()?;
+
+            if moderation.lockouts.clear(ip) {
+                eprintln!("Client {} cleared the lockout of {}", client_id, ip);
+            }
+            bin_writer.write_u8(ServerMessage::Ok as u8);
+        }
+        ClientMessage::ListOfConnections => {
+            require_admin(client_id, clients)?;
+
+            let mut connections: Vec<ConnectionInfo> = clients
+                .values()
+                .map(|client| {
+                    let (room, player) = rooms.player(client.id).unzip();
+                    ConnectionInfo {
+                        id: client.id,
+                        ip: client.peer_ip,
+                        name: player.map(|p| p.name().to_string()).unwrap_or_default(),
+                        room: room.unwrap_or_default().to_string(),
+                        in_match: player.is_some_and(|p| p.state() == PlayerState::InMatch),
+                        admin: client.admin,
+                    }
+                })
+                .collect();
+            connections.sort_by_key(|c| c.id);
+
+            // only send as many connections as fit into a single packet
+            let mut size = 3;
+            let connections: Vec<ConnectionInfo> = connections
+                .into_iter()
+                .take_while(|c| {
+                    size += c.encoded_len();
+                    size <= MAX_PACKET_LENGTH
+                })
+                .collect();
+
+            bin_writer.write_u8(ServerMessage::ListOfConnections as u8);
+            bin_writer.write_u16(connections.len() as u16);
+            for connection in connections {
+                connection.write(bin_writer);
+            }
+        }
+        ClientMessage::ListOfMatches => {
+            require_admin(client_id, clients)?;
+            // only send as many matches as fit into a single packet
+            let mut size = 3;
+            let matches: Vec<MatchInfo> = rooms
+                .matches()
+                .into_iter()
+                .take_while(|m| {
+                    size += m.encoded_len();
+                    size <= MAX_PACKET_LENGTH
+                })
+                .collect();
+
+            bin_writer.write_u8(ServerMessage::ListOfMatches as u8);
+            bin_writer.write_u16(matches.len() as u16);
+            for m in matches {
+                m.write(bin_writer);
+            }
+        }
+        ClientMessage::Kick => {
+            require_admin(client_id, clients)?;
+            let id = bin_reader.read_u32()?;
+
+            if id == client_id {
+                bail!(GameError::new(ErrorCode::CannotKickSelf, "Admins can't kick themselves"));
+            }
+            if !clients.contains_key(&id) {
+                bail!(GameError::new(ErrorCode::PlayerNotFound, format!("Player {} not found", id)));
+            }
+
+            eprintln!("Client {} kicked client {}", client_id, id);
+            remove_client_async(id, rooms, clients, bin_writer).await?;
+
+            bin_writer.clear();
+            bin_writer.write_u8(ServerMessage::Ok as u8);
+        }
+        ClientMessage::EndMatch => {
+            require_admin(client_id, clients)?;
+            let opponent_id = bin_reader.read_u32()?;
+
+            let Ok(game) = rooms.game_mut(opponent_id) else {
+                bail!(GameError::new(ErrorCode::PlayerNotFound, format!("Player {} not found", opponent_id)));
+            };
+
+            eprintln!("Client {} ended the match of client {}", client_id, opponent_id);
+            end_match_async(opponent_id, MatchOutcome::Aborted, game, clients, bin_writer).await?;
+            bin_writer.write_u8(ServerMessage::Ok as u8);
+        }
+        ClientMessage::Broadcast => {
+            require_admin(client_id, clients)?;
+            let text = bin_reader.read_str()?;
+
+            let message = ChatMessage {
+                sender_id: SERVER_ID,
+                sender_name: String::new(),
+                direct: false,
+                text,
+            };
+            bin_writer.write_u8(ServerMessage::Chat as u8);
+            message.write(bin_writer);
+
+            // everyone on the server, whatever room they are in
+            for (id, client) in clients.iter_mut() {
+                if *id != client_id {
+                    client.send_async(bin_writer).await?;
+                }
+            }
+            bin_writer.clear();
+
+            bin_writer.write_u8(ServerMessage::Ok as u8);
+        }
+        ClientMessage::Ban => {
+            require_admin(client_id, clients)?;
+            let ban = Ban::read(bin_reader)?;
+
+            moderation.bans.add(&ban);
+            eprintln!("Client {} banned {:?}", client_id, ban);
+
+            // banned players that are connected are kicked right away
+            let banned: Vec<ClientId> = clients
+                .values()
+                .filter(|client| {
+                    let name = rooms.player(client.id).map(|(_, p)| p.name()).unwrap_or_default();
+                    client.id != client_id && ban.matches(client.peer_ip, name)
+                })
+                .map(|client| client.id)
+                .collect();
+            for id in banned {
+                remove_client_async(id, rooms, clients, bin_writer).await?;
+            }
+
+            bin_writer.clear();
+            bin_writer.write_u8(ServerMessage::Ok as u8);
+        }
+        ClientMessage::Unban => {
+            require_admin(client_id, clients)?;
+            let ban = Ban::read(bin_reader)?;
+
+            if moderation.bans.remove(&ban) {
+                eprintln!("Client {} lifted the ban of {:?}", client_id, ban);
+            }
+            bin_writer.write_u8(ServerMessage::Ok as u8);
+        }
         ClientMessage::ListOfOpponents => {
+            let game = rooms.game_mut(client_id)?;
             // get list of clients and filter out the callers id
             let opponent_ids: Vec<ClientId> = game
                 .list_of_opponents()
@@ -115,22 +474,20 @@ async fn process_message(
             bin_writer.write_u8(ServerMessage::ListOfOpponents as u8);
             bin_writer.write_u16(opponent_ids.len() as u16);
             for id in opponent_ids {
-                bin_writer.write_u32(id as u32);
+                bin_writer.write_u32(id);
             }
         },
         ClientMessage::RequestMatch => {
-            let opponent_id = bin_reader.read_u32();
-            let word = bin_reader.read_str();
+            let game = rooms.game_mut(client_id)?;
+            let opponent_id = bin_reader.read_u32()?;
+            let word = bin_reader.read_str()?;
+            let mode = MatchMode::try_from(bin_reader.read_u8()?)?;
 
-            if let Some(opponent) = clients.get_mut(&opponent_id) {
+            if clients.contains_key(&opponent_id) {
 
                 // begin the message
-                game.begin_match(client_id, opponent_id, &word)?;
-
-                // send challenge message to the opponent
-                bin_writer.write_u8(ServerMessage::Challenged as u8);
-                opponent.send_async(bin_writer).await?;
-                bin_writer.clear();
+                game.begin_match(client_id, opponent_id, mode, &word)?;
+                send_challenge_async(opponent_id, game.hangman_state(opponent_id), clients, bin_writer).await?;
 
                 // respond to the caller
                 bin_writer.write_u8(ServerMessage::Ok as u8);
@@ -139,22 +496,30 @@ async fn process_message(
             }
         },
         ClientMessage::SendHint => {
-            let hint = bin_reader.read_str();
+            let game = rooms.game_mut(client_id)?;
+            let hint = bin_reader.read_str()?;
 
-            let opponent_id = game.opponents_id(client_id)?;
-            if let Some(opponent) = clients.get_mut(&opponent_id) {
-                // send hint to opponent
-                bin_writer.write_u8(ServerMessage::Hint as u8);
-                bin_writer.write_str(&hint);
-                opponent.send_async(bin_writer).await?;
+            let opponent_ids = game.opponent_ids(client_id)?;
+            game.record_hint(client_id, &hint)?;
 
-                bin_writer.clear();
+            // send hint to every opponent
+            bin_writer.write_u8(ServerMessage::Hint as u8);
+            bin_writer.write_str(&hint);
+            for opponent_id in opponent_ids {
+                if let Some(opponent) = clients.get_mut(&opponent_id) {
+                    opponent.send_async(bin_writer).await?;
+                }
             }
+            bin_writer.clear();
+
+            // respond to the caller
+            bin_writer.write_u8(ServerMessage::Ok as u8);
         },
         ClientMessage::SendAttempt => {
-            let word = bin_reader.read_str();
+            let game = rooms.game_mut(client_id)?;
+            let word = bin_reader.read_str()?;
 
-            let is_valid = if game.validate_word(client_id, &word)? { 1_u8 } else { 0 };
+            let feedback = game.validate_word(client_id, &word)?;
 
             // send attempt to challenger
             let challenger_id = game.challenger_id(client_id)?;
@@ -162,8 +527,9 @@ async fn process_message(
 
                 // send hint to opponent
                 bin_writer.write_u8(ServerMessage::Attempt as u8);
-                bin_writer.write_u8(is_valid);
+                bin_writer.write_u8(feedback.correct as u8);
                 bin_writer.write_str(&word);
+                write_letters(&feedback.letters, bin_writer);
                 challenger.send_async(bin_writer).await?;
 
                 bin_writer.clear();
@@ -171,9 +537,205 @@ async fn process_message(
 
             // send response to opponent
             bin_writer.write_u8(ServerMessage::AttemptResult as u8);
-            bin_writer.write_u8(is_valid);
+            feedback.write(bin_writer);
+
+            // the match is over once the word is guessed
+            if feedback.correct {
+                end_match_async(client_id, MatchOutcome::Solved, game, clients, bin_writer).await?;
+            } else if let Some(hint) = game.automatic_hint(client_id) {
+                // the server plays the challenger in solo matches
+                if let Some(opponent) = clients.get_mut(&client_id) {
+                    opponent.send_async(bin_writer).await?;
+                    bin_writer.clear();
+
+                    bin_writer.write_u8(ServerMessage::Hint as u8);
+                    bin_writer.write_str(&hint);
+                }
+            }
+        }
+        ClientMessage::RequestSoloMatch => {
+            let game = rooms.game_mut(client_id)?;
+            let mode = MatchMode::try_from(bin_reader.read_u8()?)?;
+            let seed = match bin_reader.read_u8()? {
+                0 => None,
+                _ => Some(bin_reader.read_u64()?),
+            };
+
+            game.begin_solo_match(client_id, mode, seed)?;
+
+            // hangman guessers start with the masked word
+            if let Some(state) = game.hangman_state(client_id) {
+                bin_writer.write_u8(ServerMessage::HangmanState as u8);
+                state.write(bin_writer);
+                if let Some(client) = clients.get_mut(&client_id) {
+                    client.send_async(bin_writer).await?;
+                }
+                bin_writer.clear();
+            }
+
+            bin_writer.write_u8(ServerMessage::Ok as u8);
+        }
+        ClientMessage::GuessLetter => {
+            let game = rooms.game_mut(client_id)?;
+            let letter = bin_reader.read_str()?;
+
+            let guess = game.guess_letter(client_id, &letter)?;
+
+            // send the guess and the new mask to the challenger
+            let challenger_id = game.challenger_id(client_id)?;
+            if let Some(challenger) = clients.get_mut(&challenger_id) {
+                bin_writer.write_u8(ServerMessage::LetterGuessed as u8);
+                bin_writer.write_str(&letter);
+                guess.write(bin_writer);
+                challenger.send_async(bin_writer).await?;
+
+                bin_writer.clear();
+            }
+
+            // send response to opponent
+            bin_writer.write_u8(ServerMessage::LetterResult as u8);
+            guess.write(bin_writer);
+
+            if guess.state.is_solved() {
+                end_match_async(client_id, MatchOutcome::Solved, game, clients, bin_writer).await?;
+            } else if guess.state.is_lost() {
+                end_match_async(client_id, MatchOutcome::Failed, game, clients, bin_writer).await?;
+            }
+        }
+        ClientMessage::SendChat => {
+            let game = rooms.game_mut(client_id)?;
+            let text = bin_reader.read_str()?;
+
+            let message = game.chat_message(client_id, &text, false)?;
+            bin_writer.write_u8(ServerMessage::Chat as u8);
+            message.write(bin_writer);
+
+            // send to everyone else in the room
+            for id in game.player_ids() {
+                if id != client_id {
+                    if let Some(client) = clients.get_mut(&id) {
+                        client.send_async(bin_writer).await?;
+                    }
+                }
+            }
+            bin_writer.clear();
+
+            bin_writer.write_u8(ServerMessage::Ok as u8);
+        }
+        ClientMessage::SendDirectMessage => {
+            let game = rooms.game_mut(client_id)?;
+            let recipient_id = bin_reader.read_u32()?;
+            let text = bin_reader.read_str()?;
+
+            if recipient_id == client_id || !clients.contains_key(&recipient_id) {
+                bail!(GameError::new(ErrorCode::RecipientNotFound, format!("Player {} not found", recipient_id)));
+            }
+
+            let message = game.chat_message(client_id, &text, true)?;
+            if let Some(recipient) = clients.get_mut(&recipient_id) {
+                bin_writer.write_u8(ServerMessage::Chat as u8);
+                message.write(bin_writer);
+                recipient.send_async(bin_writer).await?;
+                bin_writer.clear();
+            }
+
+            bin_writer.write_u8(ServerMessage::Ok as u8);
+        }
+        ClientMessage::MatchHistory => {
+            let game = rooms.game_mut(client_id)?;
+            let limit = (bin_reader.read_u16()? as usize).min(MAX_HISTORY_ENTRIES);
+            let records = game.match_history(client_id, limit)?;
+
+            // only send as many matches as fit into a single packet
+            let mut size = 3;
+            let summaries: Vec<MatchSummary> = records
+                .iter()
+                .map(|r| r.summary())
+                .take_while(|s| {
+                    size += s.encoded_len();
+                    size <= MAX_PACKET_LENGTH
+                })
+                .collect();
+
+            bin_writer.write_u8(ServerMessage::MatchHistory as u8);
+            bin_writer.write_u16(summaries.len() as u16);
+            for summary in summaries {
+                summary.write(bin_writer);
+            }
+        }
+        ClientMessage::Leaderboard => {
+            let game = rooms.game_mut(client_id)?;
+            let count = (bin_reader.read_u16()? as usize).min(MAX_LEADERBOARD_ENTRIES);
+            let entries = game.leaderboard(count)?;
+
+            bin_writer.write_u8(ServerMessage::Leaderboard as u8);
+            bin_writer.write_u16(entries.len() as u16);
+            for entry in entries {
+                bin_writer.write_str(&entry.name);
+                bin_writer.write_u32(entry.rating);
+            }
+        }
+        ClientMessage::RequestRace => {
+            let game = rooms.game_mut(client_id)?;
+            let word = bin_reader.read_str()?;
+            let mode = MatchMode::try_from(bin_reader.read_u8()?)?;
+            let count = bin_reader.read_u16()? as usize;
+            let opponent_ids: Vec<ClientId> = (0..count).map(|_| bin_reader.read_u32()).collect::<anyhow::Result<_>>()?;
+
+            game.begin_race(client_id, &opponent_ids, mode, &word)?;
+            for opponent_id in opponent_ids {
+                send_challenge_async(opponent_id, game.hangman_state(opponent_id), clients, bin_writer).await?;
+            }
+
+            bin_writer.write_u8(ServerMessage::Ok as u8);
+        }
+        ClientMessage::RequestTournament => {
+            let game = rooms.game_mut(client_id)?;
+            let format = TournamentFormat::try_from(bin_reader.read_u8()?)?;
+            let words = WordSource::try_from(bin_reader.read_u8()?)?;
+            let mode = MatchMode::try_from(bin_reader.read_u8()?)?;
+            let count = bin_reader.read_u16()? as usize;
+            let player_ids: Vec<ClientId> = (0..count).map(|_| bin_reader.read_u32()).collect::<anyhow::Result<_>>()?;
+
+            game.begin_tournament(client_id, format, words, mode, &player_ids)?;
+            if let Some(update) = game.advance_tournament()? {
+                send_tournament_update_async(update, clients, bin_writer).await?;
+            }
+
+            bin_writer.write_u8(ServerMessage::Ok as u8);
+        }
+        ClientMessage::SendTournamentWord => {
+            let game = rooms.game_mut(client_id)?;
+            let word = bin_reader.read_str()?;
+
+            let opponent_id = game.set_tournament_word(client_id, &word)?;
+            send_challenge_async(opponent_id, game.hangman_state(opponent_id), clients, bin_writer).await?;
+
+            bin_writer.write_u8(ServerMessage::Ok as u8);
+        }
+        ClientMessage::ListOfRooms => {
+            let list = rooms.list();
+
+            bin_writer.write_u8(ServerMessage::ListOfRooms as u8);
+            bin_writer.write_u16(list.len() as u16);
+            for room in list {
+                room.write(bin_writer);
+            }
+        }
+        ClientMessage::CreateRoom => {
+            let name = bin_reader.read_str()?;
+            let password = bin_reader.read_str()?;
+
+            rooms.create(client_id, &name, &password)?;
+            bin_writer.write_u8(ServerMessage::Ok as u8);
+        }
+        ClientMessage::JoinRoom => {
+            let name = bin_reader.read_str()?;
+            let password = bin_reader.read_str()?;
+
+            rooms.join(client_id, &name, &password)?;
+            bin_writer.write_u8(ServerMessage::Ok as u8);
         }
-        _ => bail!("Unexpected command '{:?}'", flag)
     }
 
 
@@ -192,9 +754,216 @@ async fn process_message(
     Ok(())
 }
 
+fn require_admin(client_id: ClientId, clients: &HashMap<u32, TcpClient>) -> anyhow::Result<()> {
+    match clients.get(&client_id) {
+        Some(client) if client.admin => Ok(()),
+        _ => bail!(GameError::new(ErrorCode::NotAdmin, "Only admins can do that")),
+    }
+}
+
+/// Tells the opponent about the match that just began.
+async fn send_challenge_async(
+    opponent_id: ClientId,
+    hangman: Option<HangmanState>,
+    clients: &mut HashMap<u32, TcpClient>,
+    bin_writer: &mut BinWriter,
+) -> anyhow::Result<()> {
+    let Some(opponent) = clients.get_mut(&opponent_id) else {
+        return Ok(());
+    };
+
+    // send challenge message to the opponent
+    bin_writer.write_u8(ServerMessage::Challenged as u8);
+    opponent.send_async(bin_writer).await?;
+    bin_writer.clear();
+
+    // hangman guessers start with the masked word
+    if let Some(state) = hangman {
+        bin_writer.write_u8(ServerMessage::HangmanState as u8);
+        state.write(bin_writer);
+        opponent.send_async(bin_writer).await?;
+        bin_writer.clear();
+    }
+
+    Ok(())
+}
+
+/// Ends the opponent's match, the response already written to `bin_writer` is sent to the opponent
+/// before the players are notified.
+async fn end_match_async(
+    opponent_id: ClientId,
+    outcome: MatchOutcome,
+    game: &mut Game,
+    clients: &mut HashMap<u32, TcpClient>,
+    bin_writer: &mut BinWriter,
+) -> anyhow::Result<()> {
+    let finished = game.end_match(opponent_id, outcome)?;
+
+    if let Some(opponent) = clients.get_mut(&opponent_id) {
+        opponent.send_async(bin_writer).await?;
+        bin_writer.clear();
+    }
+
+    for m in finished {
+        notify_match_ended(&m, clients, bin_writer).await?;
+    }
+
+    // the next tournament matches can begin once the players are free
+    if let Some(update) = game.advance_tournament()? {
+        send_tournament_update_async(update, clients, bin_writer).await?;
+    }
+    Ok(())
+}
+
+async fn send_tournament_update_async(
+    update: TournamentUpdate,
+    clients: &mut HashMap<u32, TcpClient>,
+    bin_writer: &mut BinWriter,
+) -> anyhow::Result<()> {
+    bin_writer.clear();
+    bin_writer.write_u8(ServerMessage::TournamentBracket as u8);
+    bin_writer.write_u16(update.pairings.len() as u16);
+    for pairing in &update.pairings {
+        pairing.write(bin_writer);
+    }
+    for id in &update.participants {
+        if let Some(client) = clients.get_mut(id) {
+            client.send_async(bin_writer).await?;
+        }
+    }
+    bin_writer.clear();
+
+    for (opponent_id, hangman) in update.started {
+        send_challenge_async(opponent_id, hangman, clients, bin_writer).await?;
+    }
+
+    // challengers of matches with player words pick the word first
+    for (challenger_id, opponent_id) in update.word_requests {
+        if let Some(challenger) = clients.get_mut(&challenger_id) {
+            bin_writer.write_u8(ServerMessage::TournamentWordRequest as u8);
+            bin_writer.write_u32(opponent_id);
+            challenger.send_async(bin_writer).await?;
+            bin_writer.clear();
+        }
+    }
+
+    if let Some(winner) = update.winner {
+        bin_writer.write_u8(ServerMessage::TournamentWinner as u8);
+        bin_writer.write_u32(winner);
+        for id in &update.participants {
+            if let Some(client) = clients.get_mut(id) {
+                client.send_async(bin_writer).await?;
+            }
+        }
+        bin_writer.clear();
+    }
+
+    Ok(())
+}
+
+/// Removes a client from the server, ends its matches and tells its connection task to close.
+/// Clients that were already removed are skipped.
+async fn remove_client_async(
+    client_id: ClientId,
+    rooms: &mut Rooms,
+    clients: &mut HashMap<u32, TcpClient>,
+    bin_writer: &mut BinWriter,
+) -> anyhow::Result<()> {
+    let Some(mut client) = clients.remove(&client_id) else {
+        return Ok(());
+    };
+    client.record_disconnected();
+    // the connection may be closed already
+    _ = client.disconnect_async().await;
+
+    let (finished, tournament) = rooms.remove_player(client_id)?;
+    for m in finished {
+        if let Err(err) = notify_match_ended(&m, clients, bin_writer).await {
+            eprintln!("Couldn't notify players: {}", err);
+        }
+    }
+
+    if let Some(update) = tournament {
+        if let Err(err) = send_tournament_update_async(update, clients, bin_writer).await {
+            eprintln!("Couldn't notify players: {}", err);
+        }
+    }
+
+    Ok(())
+}
+
+async fn notify_match_ended(
+    finished: &FinishedMatch,
+    clients: &mut HashMap<u32, TcpClient>,
+    bin_writer: &mut BinWriter,
+) -> anyhow::Result<()> {
+    bin_writer.clear();
+    bin_writer.write_u8(ServerMessage::MatchEnded as u8);
+    finished.score.write(bin_writer);
+
+    for id in [finished.challenger_id, finished.opponent_id] {
+        if let Some(client) = clients.get_mut(&id) {
+            client.send_async(bin_writer).await?;
+        }
+    }
+
+    // everyone in a race gets the final standings once its last match ended
+    if let Some(race) = &finished.race {
+        bin_writer.clear();
+        bin_writer.write_u8(ServerMessage::RaceStandings as u8);
+        bin_writer.write_u16(race.standings.len() as u16);
+        for standing in &race.standings {
+            standing.write(bin_writer);
+        }
+
+        for id in &race.participants {
+            if let Some(client) = clients.get_mut(id) {
+                client.send_async(bin_writer).await?;
+            }
+        }
+    }
+
+    bin_writer.clear();
+    Ok(())
+}
+
+/// Answers an authorization whose account password was verified off the server loop.
+async fn finish_authorization_async(
+    verification: AccountVerification,
+    rooms: &mut Rooms,
+    clients: &mut HashMap<u32, TcpClient>,
+    moderation: &mut Moderation,
+    bin_writer: &mut BinWriter,
+) -> anyhow::Result<()> {
+    let AccountVerification { client_id, name, verified } = verification;
+
+    // the client may have disconnected meanwhile
+    let Some(client) = clients.get_mut(&client_id) else {
+        return Ok(());
+    };
+    client.authorizing = false;
+
+    bin_writer.clear();
+    if verified && rooms.set_player_name(client_id, &name) {
+        moderation.lockouts.record_success(client.peer_ip);
+        client.authorized = true;
+        bin_writer.write_u32(client_id);
+        client.send_async(bin_writer).await?;
+    } else {
+        moderation.lockouts.record_failure(client.peer_ip);
+        client.send_async(bin_writer).await?;
+        client.disconnect_async().await?;
+    }
+
+    Ok(())
+}
+
 async fn accept_client(
     client_id: ClientId,
-    stream: TcpStream,
+    connection: Connection,
+    tls: Option<TlsAcceptor>,
+    capture: Option<CaptureWriter>,
+    mut throttle: MessageThrottle,
     mut in_sender: UnboundedSender<TcpMessage>,
     client_list: &mut HashMap<u32, TcpClient>,
 ) -> anyhow::Result<()> {
@@ -202,12 +971,31 @@ async fn accept_client(
     let (out_sender, mut out_receiver) = futures::channel::mpsc::unbounded();
 
     // insert the client to the server list
-    client_list.insert(client_id, TcpClient::new(client_id, out_sender));
+    if let Some(capture) = &capture {
+        capture.record(client_id, CaptureKind::Connected, &[]);
+    }
+    let mut client = TcpClient::new(client_id, connection.peer_ip, out_sender, capture.clone());
+
+    // queue the initial request authorization message, it's sent once the connection is set up
+    let mut bin_writer = BinWriter::with_capacity(1);
+    bin_writer.write_u8(ServerMessage::RequestAuthorization as u8);
+    client.send_async(&bin_writer).await?;
+
+    client_list.insert(client_id, client);
 
     // start processing IO for the newly accepted client
     tokio::spawn(async move {
-        if let Err(err) = process_client(client_id, stream, &mut in_sender, &mut out_receiver).await
-        {
+        // the TLS handshake happens here so a slow client can't hold up the server loop
+        let Connection { stream, transport, .. } = connection;
+        let result = match tls {
+            Some(acceptor) => match acceptor.accept(stream).await {
+                Ok(stream) => process_stream(client_id, stream, transport, capture, &mut throttle, &mut in_sender, &mut out_receiver).await,
+                Err(err) => Err(err.into()),
+            },
+            None => process_stream(client_id, stream, transport, capture, &mut throttle, &mut in_sender, &mut out_receiver).await,
+        };
+
+        if let Err(err) = result {
             eprintln!("Error processing client: {}", err);
         }
 
@@ -217,19 +1005,77 @@ async fn accept_client(
     Ok(())
 }
 
-async fn process_client(
+/// Runs the protocol the listener speaks over an accepted stream.
+async fn process_stream<S: AsyncRead + AsyncWrite + Unpin>(
     client_id: ClientId,
-    stream: TcpStream,
+    stream: S,
+    transport: Transport,
+    capture: Option<CaptureWriter>,
+    throttle: &mut MessageThrottle,
     sender: &mut UnboundedSender<TcpMessage>,
     receiver: &mut UnboundedReceiver<TcpMessage>,
 ) -> anyhow::Result<()> {
-    let mut framed_stream = create_framed_stream(stream);
+    let capture = capture.as_ref();
+    match transport {
+        Transport::Framed => {
+            let framed_stream = SinkExt::<Bytes>::sink_map_err(create_framed_stream(stream), anyhow::Error::from)
+                .map_ok(BytesMut::freeze)
+                .map_err(anyhow::Error::from);
 
-    // send initial request authorization message
-    let mut bin_writer = BinWriter::with_capacity(1);
-    bin_writer.write_u8(ServerMessage::RequestAuthorization as u8);
-    framed_stream.send(Bytes::from(bin_writer.clone_data())) .await?;
+            process_client(client_id, framed_stream, capture, throttle, sender, receiver).await
+        }
+        Transport::WebSocket => {
+            let config = WebSocketConfig::default().max_message_size(Some(MAX_PACKET_LENGTH));
+            let websocket = tokio_tungstenite::accept_async_with_config(stream, Some(config)).await?;
 
+            // every binary message carries one frame, anything else is ignored
+            let framed_stream = websocket
+                .sink_map_err(anyhow::Error::from)
+                .with(|msg: Bytes| future::ready(Ok::<_, anyhow::Error>(WsMessage::Binary(msg))))
+                .map_err(anyhow::Error::from)
+                .try_filter_map(|msg| future::ready(Ok(match msg {
+                    WsMessage::Binary(data) => Some(data),
+                    _ => None,
+                })));
+
+            process_client(client_id, Box::pin(framed_stream), capture, throttle, sender, receiver).await
+        }
+        Transport::Json => {
+            // the id sent after authorization is the only frame without a flag
+            let mut awaiting_id = false;
+            let framed_stream = Framed::new(stream, LinesCodec::new_with_max_length(MAX_JSON_LINE_LENGTH))
+                .with(move |frame: Bytes| {
+                    let line = if awaiting_id && frame.len() == 4 {
+                        awaiting_id = false;
+                        BinReader::from_bytes(&frame).read_u32().map(authorized_to_json)
+                    } else {
+                        awaiting_id |= frame.first() == Some(&(ServerMessage::RequestAuthorization as u8));
+                        server_message_to_json(&frame)
+                    };
+                    future::ready(line)
+                })
+                .map_err(anyhow::Error::from)
+                .try_filter_map(|line| future::ready(match line.trim() {
+                    "" => Ok(None),
+                    line => client_message_from_json(line).map(|frame| Some(Bytes::from(frame))),
+                }));
+
+            process_client(client_id, framed_stream, capture, throttle, sender, receiver).await
+        }
+    }
+}
+
+async fn process_client<T>(
+    client_id: ClientId,
+    mut framed_stream: T,
+    capture: Option<&CaptureWriter>,
+    throttle: &mut MessageThrottle,
+    sender: &mut UnboundedSender<TcpMessage>,
+    receiver: &mut UnboundedReceiver<TcpMessage>,
+) -> anyhow::Result<()>
+where
+    T: Stream<Item = anyhow::Result<Bytes>> + Sink<Bytes, Error = anyhow::Error> + Unpin,
+{
     loop {
         tokio::select! {
             // process outgoing messages - server -> client
@@ -240,17 +1086,38 @@ async fn process_client(
                     },
                     TcpMessage::Disconnect(_) => {
                         framed_stream.close().await?;
+                        return Ok(());
                     },
                 }
             },
             // process incoming message - client -> server
         result = framed_stream.next() => match result {
             Some(Ok(msg)) => {
-                sender.send(TcpMessage::Message(client_id, msg.into())).await?;
+                // recorded here so frames the throttle drops end up in the capture too
+                if let Some(capture) = capture {
+                    capture.record(client_id, CaptureKind::Inbound, &msg);
+                }
+
+                // throttled messages are answered here, they never reach the server loop
+                if let Err(err) = throttle.check_message() {
+                    let response = Bytes::from(vec![ServerMessage::Err as u8, GameError::code_of(&err) as u8]);
+                    if let Some(capture) = capture {
+                        capture.record(client_id, CaptureKind::Outbound, &response);
+                    }
+                    framed_stream.send(response).await?;
+
+                    if throttle.should_disconnect() {
+                        eprintln!("Disconnecting client {} for ignoring rate limits", client_id);
+                        return Ok(());
+                    }
+                    continue;
+                }
+
+                sender.send(TcpMessage::Message(client_id, msg)).await?;
             },
             // an error occurred
             Some(Err(e)) => {
-                return Err(e.into());
+                return Err(e);
             }
                 // socket was closed
             None => {
@@ -0,0 +1,84 @@
+use crate::bin_reader::BinReader;
+use crate::bin_writer::BinWriter;
+use crate::server::history::{MatchEventKind, MatchOutcome, MatchRecord};
+use crate::server::scoring::MatchScore;
+use crate::utils::ClientId;
+
+/// How a single guesser did in a race.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RaceStanding {
+    pub guesser_id: ClientId,
+    pub guesser_name: String,
+    pub outcome: MatchOutcome,
+    /// Words and letters the guesser tried.
+    pub guesses: u16,
+    pub score: u32,
+}
+
+impl RaceStanding {
+    pub fn new(record: &MatchRecord, score: &MatchScore) -> RaceStanding {
+        RaceStanding {
+            guesser_id: record.opponent_id,
+            guesser_name: record.opponent_name.clone(),
+            outcome: record.outcome,
+            guesses: record.events.iter().filter(|e| e.kind != MatchEventKind::Hint).count() as u16,
+            score: score.guesser,
+        }
+    }
+
+    pub fn write(&self, bin_writer: &mut BinWriter) {
+        bin_writer.write_u32(self.guesser_id);
+        bin_writer.write_str(&self.guesser_name);
+        bin_writer.write_u8(self.outcome as u8);
+        bin_writer.write_u16(self.guesses);
+        bin_writer.write_u32(self.score);
+    }
+
+    pub fn read(bin_reader: &mut BinReader) -> anyhow::Result<RaceStanding> {
+        Ok(RaceStanding {
+            guesser_id: bin_reader.read_u32()?,
+            guesser_name: bin_reader.read_str()?,
+            outcome: MatchOutcome::try_from(bin_reader.read_u8()?)?,
+            guesses: bin_reader.read_u16()?,
+            score: bin_reader.read_u32()?,
+        })
+    }
+}
+
+/// A challenger's word raced by several guessers, every guesser plays its own match.
+pub struct Race {
+    pub challenger_id: ClientId,
+    guesser_ids: Vec<ClientId>,
+    standings: Vec<RaceStanding>,
+}
+
+impl Race {
+    pub fn new(challenger_id: ClientId, guesser_ids: Vec<ClientId>) -> Race {
+        Race {
+            challenger_id,
+            guesser_ids,
+            standings: Vec::new(),
+        }
+    }
+
+    pub fn add_standing(&mut self, standing: RaceStanding) {
+        self.standings.push(standing);
+    }
+
+    /// Standings with the winner first, the other guessers in the order their matches ended.
+    pub fn finish(self) -> RaceResult {
+        let mut standings = self.standings;
+        standings.sort_by_key(|s| s.outcome != MatchOutcome::Solved);
+
+        let mut participants = vec![self.challenger_id];
+        participants.extend(self.guesser_ids);
+
+        RaceResult { participants, standings }
+    }
+}
+
+/// Final standings of a race, sent to everyone who took part.
+pub struct RaceResult {
+    pub participants: Vec<ClientId>,
+    pub standings: Vec<RaceStanding>,
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use crate::bin_reader::BinReader;
+use crate::bin_writer::BinWriter;
+use crate::server::history::{MatchOutcome, MatchRecord};
+
+pub const DEFAULT_RATING: u32 = 1200;
+
+/// How much a single match can move a rating.
+const K_FACTOR: f64 = 32.0;
+
+/// A solved match counts as a full win when guessed instantly and drops to a draw at this duration.
+const SOLVE_TIME_WINDOW_MS: f64 = 5.0 * 60.0 * 1000.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeaderboardEntry {
+    pub name: String,
+    pub rating: u32,
+}
+
+/// Elo ratings of named players, rewritten to disk after every change.
+pub struct Ratings {
+    path: Option<PathBuf>,
+    ratings: HashMap<String, u32>,
+}
+
+impl Ratings {
+    pub fn load(path: Option<PathBuf>) -> anyhow::Result<Ratings> {
+        let mut ratings = HashMap::new();
+
+        if let Some(path) = &path {
+            match std::fs::read(path) {
+                Ok(data) => {
+                    let mut bin_reader = BinReader::from_slice(&data);
+                    let count = bin_reader.read_u32()?;
+                    for _ in 0..count {
+                        let name = bin_reader.read_str()?;
+                        ratings.insert(name, bin_reader.read_u32()?);
+                    }
+                }
+                Err(err) if err.kind() == ErrorKind::NotFound => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(Ratings { path, ratings })
+    }
+
+    pub fn rating(&self, name: &str) -> u32 {
+        self.ratings.get(name).copied().unwrap_or(DEFAULT_RATING)
+    }
+
+    /// Updates both players' ratings from a finished match. Matches involving unnamed players are unrated.
+    pub fn update(&mut self, record: &MatchRecord) -> anyhow::Result<()> {
+        if record.challenger_name.is_empty() || record.opponent_name.is_empty() {
+            return Ok(());
+        }
+
+        let Some(score) = guesser_score(record) else {
+            return Ok(());
+        };
+
+        let opponent_rating = self.rating(&record.opponent_name) as f64;
+        let challenger_rating = self.rating(&record.challenger_name) as f64;
+        let expected = 1.0 / (1.0 + 10_f64.powf((challenger_rating - opponent_rating) / 400.0));
+        let change = K_FACTOR * (score - expected);
+
+        self.ratings.insert(record.opponent_name.clone(), adjust(opponent_rating, change));
+        self.ratings.insert(record.challenger_name.clone(), adjust(challenger_rating, -change));
+
+        self.save()
+    }
+
+    pub fn top(&self, count: usize) -> Vec<LeaderboardEntry> {
+        let mut entries: Vec<LeaderboardEntry> = self.ratings
+            .iter()
+            .map(|(name, rating)| LeaderboardEntry { name: name.clone(), rating: *rating })
+            .collect();
+
+        entries.sort_by(|a, b| b.rating.cmp(&a.rating).then_with(|| a.name.cmp(&b.name)));
+        entries.truncate(count);
+        entries
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let mut bin_writer = BinWriter::with_capacity(256);
+        bin_writer.write_u32(self.ratings.len() as u32);
+        for (name, rating) in &self.ratings {
+            bin_writer.write_str(name);
+            bin_writer.write_u32(*rating);
+        }
+
+        // write to a temporary file first so a crash can't leave a truncated file behind
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, bin_writer.clone_data())?;
+        std::fs::rename(&tmp_path, path)?;
+
+        Ok(())
+    }
+}
+
+/// Result of the match from the guesser's point of view, 1 is a win and 0 a loss.
+fn guesser_score(record: &MatchRecord) -> Option<f64> {
+    match record.outcome {
+        MatchOutcome::Solved => {
+            let elapsed = record.ended_at.saturating_sub(record.started_at) as f64;
+            let speed = (1.0 - elapsed / SOLVE_TIME_WINDOW_MS).max(0.0);
+            Some(0.5 + 0.5 * speed)
+        },
+        // leaving counts as giving up
+        MatchOutcome::OpponentLeft | MatchOutcome::Failed => Some(0.0),
+        // losing a race says nothing about the challenger's word
+        MatchOutcome::ChallengerLeft | MatchOutcome::Outraced | MatchOutcome::Aborted => None,
+    }
+}
+
+fn adjust(rating: f64, change: f64) -> u32 {
+    (rating + change).round().max(0.0) as u32
+}
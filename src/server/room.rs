@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use anyhow::bail;
+use crate::bin_reader::BinReader;
+use crate::bin_writer::BinWriter;
+use crate::server::accounts::Accounts;
+use crate::server::admin::MatchInfo;
+use crate::server::config::{GameRules, ServerConfig};
+use crate::server::error::{ErrorCode, GameError};
+use crate::server::game::{FinishedMatch, Game, Player, TournamentUpdate, MAX_NAME_LENGTH};
+use crate::server::history::MatchHistory;
+use crate::server::ratings::Ratings;
+use crate::utils::ClientId;
+
+/// Room every player starts in after connecting.
+pub const LOBBY_ROOM: &str = "lobby";
+
+pub const MAX_ROOM_NAME_LENGTH: usize = 32;
+
+/// Room as it is listed to the players.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoomInfo {
+    pub name: String,
+    pub has_password: bool,
+    pub players: u16,
+}
+
+impl RoomInfo {
+    pub fn write(&self, bin_writer: &mut BinWriter) {
+        bin_writer.write_str(&self.name);
+        bin_writer.write_u8(self.has_password as u8);
+        bin_writer.write_u16(self.players);
+    }
+
+    pub fn read(bin_reader: &mut BinReader) -> anyhow::Result<RoomInfo> {
+        Ok(RoomInfo {
+            name: bin_reader.read_str()?,
+            has_password: bin_reader.read_u8()? > 0,
+            players: bin_reader.read_u16()?,
+        })
+    }
+}
+
+struct Room {
+    password: Option<String>,
+    // rooms created by players are closed once the last player leaves
+    persistent: bool,
+    game: Game,
+}
+
+/// Named rooms, each with its own players and matches. History and ratings are shared by all of them.
+pub struct Rooms {
+    rooms: HashMap<String, Room>,
+    player_rooms: HashMap<ClientId, String>,
+    // WARN: should be a hash of the password
+    password: String,
+    rules: GameRules,
+    accounts: Arc<Mutex<Accounts>>,
+    history: MatchHistory,
+    ratings: Arc<Mutex<Ratings>>,
+}
+
+impl Rooms {
+    pub fn new(config: &ServerConfig) -> anyhow::Result<Rooms> {
+        let mut rooms = Rooms {
+            rooms: HashMap::new(),
+            player_rooms: HashMap::new(),
+            password: config.password.clone(),
+            rules: config.rules.clone(),
+            accounts: Arc::new(Mutex::new(Accounts::load(config.accounts_path.clone())?)),
+            history: MatchHistory::load(config.history_path.clone())?,
+            ratings: Arc::new(Mutex::new(Ratings::load(config.ratings_path.clone())?)),
+        };
+
+        rooms.open(LOBBY_ROOM, None, &config.rules, true)?;
+        for room in &config.rooms {
+            rooms.open(&room.name, room.password.clone(), &room.rules, true)?;
+        }
+
+        Ok(rooms)
+    }
+
+    /// Adds a newly connected player to the lobby.
+    pub fn add_player(&mut self, id: ClientId) {
+        if let Some(lobby) = self.rooms.get_mut(LOBBY_ROOM) {
+            lobby.game.add_player(id);
+            self.player_rooms.insert(id, LOBBY_ROOM.to_string());
+        }
+    }
+
+    /// Checks the server password and whether the name can be used, names have to be unique among
+    /// connected players. The account password of a name is verified separately.
+    pub fn check_authorization(&self, id: ClientId, password: &str, name: &str) -> bool {
+        self.password == password
+            && name.len() <= MAX_NAME_LENGTH
+            && !self.is_name_taken(id, name)
+    }
+
+    /// Assigns the account name once it's verified, fails if another player took it meanwhile.
+    pub fn set_player_name(&mut self, id: ClientId, name: &str) -> bool {
+        if self.is_name_taken(id, name) {
+            return false;
+        }
+
+        match self.game_mut(id) {
+            Ok(game) => game.set_player_name(id, name),
+            Err(_) => false
+        }
+    }
+
+    /// Accounts shared with the tasks verifying their passwords, an account is registered with
+    /// the password it's first used with.
+    pub fn accounts(&self) -> Arc<Mutex<Accounts>> {
+        self.accounts.clone()
+    }
+
+    /// Game of the room the player is in.
+    pub fn game_mut(&mut self, id: ClientId) -> anyhow::Result<&mut Game> {
+        let Some(name) = self.player_rooms.get(&id) else {
+            bail!("Player {} not found.", id);
+        };
+
+        match self.rooms.get_mut(name) {
+            Some(room) => Ok(&mut room.game),
+            None => bail!("Room {} not found", name)
+        }
+    }
+
+    /// The player with the name of the room it's in.
+    pub fn player(&self, id: ClientId) -> Option<(&str, &Player)> {
+        let name = self.player_rooms.get(&id)?;
+        let player = self.rooms.get(name)?.game.player(id)?;
+        Some((name, player))
+    }
+
+    /// Matches being played in every room, for admins.
+    pub fn matches(&self) -> Vec<MatchInfo> {
+        let mut matches: Vec<MatchInfo> = self.rooms
+            .iter()
+            .flat_map(|(name, room)| room.game.match_infos(name))
+            .collect();
+
+        matches.sort_by_key(|m| m.started_at);
+        matches
+    }
+
+    /// Removes a player that disconnected, along with the matches it ended and how the room's tournament moved on.
+    pub fn remove_player(&mut self, id: ClientId) -> anyhow::Result<(Vec<FinishedMatch>, Option<TournamentUpdate>)> {
+        let Some(name) = self.player_rooms.remove(&id) else {
+            bail!("Player {} not found.", id);
+        };
+
+        let game = &mut self.room_mut(&name)?.game;
+        let finished = game.remove_player(id)?;
+        let tournament = game.advance_tournament()?;
+        self.close_if_empty(&name);
+        Ok((finished, tournament))
+    }
+
+    /// Forfeits the tournament legs that waited too long for a word, in every room.
+    pub fn expire_tournament_words(&mut self) -> Vec<TournamentUpdate> {
+        let mut updates = Vec::new();
+        for (name, room) in &mut self.rooms {
+            match room.game.expire_tournament_words() {
+                Ok(Some(update)) => updates.push(update),
+                Ok(None) => {}
+                Err(err) => eprintln!("Couldn't advance the tournament of room {}: {}", name, err),
+            }
+        }
+        updates
+    }
+
+    pub fn list(&self) -> Vec<RoomInfo> {
+        let mut rooms: Vec<RoomInfo> = self.rooms
+            .iter()
+            .map(|(name, room)| RoomInfo {
+                name: name.clone(),
+                has_password: room.password.is_some(),
+                players: room.game.player_count() as u16,
+            })
+            .collect();
+
+        rooms.sort_by(|a, b| a.name.cmp(&b.name));
+        rooms
+    }
+
+    /// Opens a room with the server's rules and moves the player into it.
+    pub fn create(&mut self, id: ClientId, name: &str, password: &str) -> anyhow::Result<()> {
+        let rules = self.rules.clone();
+        self.open(name, Some(password.to_string()), &rules, false)?;
+
+        if let Err(err) = self.join(id, name, password) {
+            self.close_if_empty(name);
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    /// Moves the player from its current room into another one, players can't leave during a match or a tournament.
+    pub fn join(&mut self, id: ClientId, name: &str, password: &str) -> anyhow::Result<()> {
+        let Some(room) = self.rooms.get(name) else {
+            bail!(GameError::new(ErrorCode::RoomNotFound, format!("Room '{}' not found", name)));
+        };
+
+        if room.password.as_ref().is_some_and(|p| p != password) {
+            bail!(GameError::new(ErrorCode::RoomPasswordWrong, format!("Wrong password for room '{}'", name)));
+        }
+
+        let Some(current) = self.player_rooms.get(&id).cloned() else {
+            bail!("Player {} not found.", id);
+        };
+
+        if current == name {
+            return Ok(());
+        }
+
+        let player = self.room_mut(&current)?.game.take_player(id)?;
+        self.room_mut(name)?.game.insert_player(player);
+        self.player_rooms.insert(id, name.to_string());
+        self.close_if_empty(&current);
+
+        Ok(())
+    }
+
+    fn open(&mut self, name: &str, password: Option<String>, rules: &GameRules, persistent: bool) -> anyhow::Result<()> {
+        if name.trim().is_empty() || name.chars().count() > MAX_ROOM_NAME_LENGTH {
+            bail!(GameError::new(
+                ErrorCode::RoomNameInvalid,
+                format!("Room names have to be 1 to {} characters long", MAX_ROOM_NAME_LENGTH)));
+        }
+
+        if self.rooms.contains_key(name) {
+            bail!(GameError::new(ErrorCode::RoomExists, format!("Room '{}' already exists", name)));
+        }
+
+        let game = Game::new(rules, self.history.clone(), self.ratings.clone())?;
+        self.rooms.insert(name.to_string(), Room {
+            password: password.filter(|p| !p.is_empty()),
+            persistent,
+            game,
+        });
+
+        Ok(())
+    }
+
+    fn close_if_empty(&mut self, name: &str) {
+        if self.rooms.get(name).is_some_and(|r| !r.persistent && r.game.player_count() == 0) {
+            self.rooms.remove(name);
+        }
+    }
+
+    fn is_name_taken(&self, id: ClientId, name: &str) -> bool {
+        !name.is_empty() && self.rooms.values().any(|r| r.game.is_name_taken(id, name))
+    }
+
+    fn room_mut(&mut self, name: &str) -> anyhow::Result<&mut Room> {
+        match self.rooms.get_mut(name) {
+            Some(room) => Ok(room),
+            None => bail!("Room {} not found", name)
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::HashSet;
+use crate::bin_reader::BinReader;
+use crate::bin_writer::BinWriter;
+use crate::server::history::{MatchEventKind, MatchOutcome, MatchRecord};
+use crate::server::text::AnswerNormalization;
+
+/// Weights of the scoring formula. Only solved matches are scored.
+#[derive(Debug, Clone)]
+pub struct ScoringConfig {
+    /// Points the guesser gets before any penalties are applied.
+    pub guesser_base: u32,
+    /// Subtracted from the guesser for every attempt after the first one and every wrong letter.
+    pub attempt_penalty: u32,
+    /// Subtracted from the guesser for every second the match took.
+    pub second_penalty: u32,
+    /// Subtracted from the guesser for every hint the challenger sent.
+    pub hint_penalty: u32,
+    /// Awarded to the challenger for every distinct letter of the word.
+    pub letter_points: u32,
+    /// Awarded to the challenger for every wrong attempt or letter the guesser needed.
+    pub failed_attempt_points: u32,
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        ScoringConfig {
+            guesser_base: 1000,
+            attempt_penalty: 100,
+            second_penalty: 2,
+            hint_penalty: 150,
+            letter_points: 20,
+            failed_attempt_points: 50,
+        }
+    }
+}
+
+/// Final result of a match, sent to both players when it ends.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MatchScore {
+    pub outcome: MatchOutcome,
+    pub guesser: u32,
+    pub challenger: u32,
+}
+
+impl MatchScore {
+    pub fn write(&self, bin_writer: &mut BinWriter) {
+        bin_writer.write_u8(self.outcome as u8);
+        bin_writer.write_u32(self.guesser);
+        bin_writer.write_u32(self.challenger);
+    }
+
+    pub fn read(bin_reader: &mut BinReader) -> anyhow::Result<MatchScore> {
+        Ok(MatchScore {
+            outcome: MatchOutcome::try_from(bin_reader.read_u8()?)?,
+            guesser: bin_reader.read_u32()?,
+            challenger: bin_reader.read_u32()?,
+        })
+    }
+}
+
+impl ScoringConfig {
+    /// Scores the match, letters guessed in hangman are compared with the word through `answers`.
+    pub fn score(&self, record: &MatchRecord, answers: &AnswerNormalization) -> MatchScore {
+        if record.outcome != MatchOutcome::Solved {
+            return MatchScore {
+                outcome: record.outcome,
+                guesser: 0,
+                challenger: 0,
+            };
+        }
+
+        let count = |kind| record.events.iter().filter(|e| e.kind == kind).count() as u32;
+        let attempts = count(MatchEventKind::Attempt);
+        let hints = count(MatchEventKind::Hint);
+        let seconds = (record.ended_at.saturating_sub(record.started_at) / 1000) as u32;
+        let secret = answers.apply(&record.word);
+        let wrong_letters = record.events
+            .iter()
+            .filter(|e| e.kind == MatchEventKind::Letter && !secret.contains(e.text.as_str()))
+            .count() as u32;
+        // a wrong letter costs the same as a wrong attempt
+        let failed_attempts = attempts.saturating_sub(1).saturating_add(wrong_letters);
+
+        let guesser = self.guesser_base
+            .saturating_sub(failed_attempts.saturating_mul(self.attempt_penalty))
+            .saturating_sub(seconds.saturating_mul(self.second_penalty))
+            .saturating_sub(hints.saturating_mul(self.hint_penalty));
+
+        let letters = record.word
+            .chars()
+            .flat_map(|c| c.to_lowercase())
+            .collect::<HashSet<char>>()
+            .len() as u32;
+        let challenger = letters.saturating_mul(self.letter_points)
+            .saturating_add(failed_attempts.saturating_mul(self.failed_attempt_points));
+
+        MatchScore {
+            outcome: record.outcome,
+            guesser,
+            challenger,
+        }
+    }
+}
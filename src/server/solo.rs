@@ -0,0 +1,93 @@
+use std::path::PathBuf;
+use crate::server::dictionary::read_word_list;
+use crate::server::hangman::HIDDEN_LETTER;
+use crate::utils::ClientId;
+
+/// Challenger id of matches where the server picks the word.
+pub const SERVER_ID: ClientId = 0;
+
+#[derive(Debug, Clone)]
+pub struct SoloRules {
+    /// Lists the server picks words from, solo matches are unavailable when they are empty.
+    pub word_lists: Vec<PathBuf>,
+    /// Seeds the server's word picks when a request doesn't bring its own seed.
+    pub seed: Option<u64>,
+    /// The server reveals another letter after this many failed attempts, 0 disables hints.
+    pub hint_after: usize,
+}
+
+impl Default for SoloRules {
+    fn default() -> Self {
+        SoloRules {
+            word_lists: Vec::new(),
+            seed: None,
+            hint_after: 3,
+        }
+    }
+}
+
+pub struct WordPool {
+    words: Vec<String>,
+    rng: SplitMix64,
+}
+
+impl WordPool {
+    pub fn load(rules: &SoloRules, default_seed: u64) -> anyhow::Result<WordPool> {
+        let mut words = Vec::new();
+        for path in &rules.word_lists {
+            words.extend(read_word_list(path)?);
+        }
+        words.sort();
+        words.dedup();
+
+        Ok(WordPool {
+            words,
+            rng: SplitMix64(rules.seed.unwrap_or(default_seed)),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Picks a word, the same seed always picks the same word from the same lists.
+    pub fn pick(&mut self, seed: Option<u64>) -> Option<&str> {
+        if self.words.is_empty() {
+            return None;
+        }
+
+        let value = match seed {
+            Some(seed) => SplitMix64(seed).next(),
+            None => self.rng.next(),
+        };
+
+        Some(&self.words[(value % self.words.len() as u64) as usize])
+    }
+}
+
+/// Hint revealing the first `revealed` letters of the word, at least one letter stays hidden.
+pub fn reveal_hint(word: &str, revealed: usize) -> Option<String> {
+    let length = word.chars().count();
+    if revealed >= length {
+        return None;
+    }
+
+    Some(word
+        .chars()
+        .enumerate()
+        .map(|(i, c)| if i < revealed { c } else { HIDDEN_LETTER })
+        .collect())
+}
+
+/// Small deterministic generator, enough to pick words without pulling in a rng crate.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+}
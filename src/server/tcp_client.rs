@@ -1,20 +1,39 @@
+use std::net::IpAddr;
 use futures::channel::mpsc::UnboundedSender;
 use futures::SinkExt;
 use tokio_util::bytes::Bytes;
 use crate::bin_writer::BinWriter;
+use crate::capture::{CaptureKind, CaptureWriter};
 use crate::server::TcpMessage;
 use crate::utils::ClientId;
 
 pub struct TcpClient {
     pub id: ClientId,
-    sender: UnboundedSender<TcpMessage>
+    pub peer_ip: Option<IpAddr>,
+    /// Whether the client sent the server password, every other command is refused until then.
+    pub authorized: bool,
+    /// Whether the account password the client sent is still being verified.
+    pub authorizing: bool,
+    /// Whether the client sent the admin password.
+    pub admin: bool,
+    sender: UnboundedSender<TcpMessage>,
+    capture: Option<CaptureWriter>,
 }
 
 impl TcpClient {
-    pub fn new(id: ClientId, sender: UnboundedSender<TcpMessage>) -> TcpClient {
+    pub fn new(
+        id: ClientId,
+        peer_ip: Option<IpAddr>,
+        sender: UnboundedSender<TcpMessage>,
+        capture: Option<CaptureWriter>) -> TcpClient {
         TcpClient {
             id,
-            sender
+            peer_ip,
+            authorized: false,
+            authorizing: false,
+            admin: false,
+            sender,
+            capture,
         }
     }
 
@@ -27,8 +46,19 @@ impl TcpClient {
         Ok(())
     }
 
+    /// Records that the client left the server.
+    pub fn record_disconnected(&self) {
+        if let Some(capture) = &self.capture {
+            capture.record(self.id, CaptureKind::Disconnected, &[]);
+        }
+    }
+
     pub async fn send_async(&mut self, bin_writer: &BinWriter) -> anyhow::Result<()> {
         if bin_writer.len() > 0 {
+            if let Some(capture) = &self.capture {
+                capture.record(self.id, CaptureKind::Outbound, &bin_writer.clone_data());
+            }
+
             self
                 .sender
                 .send(TcpMessage::Message(
@@ -0,0 +1,66 @@
+use caseless::default_case_fold_str;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NormalizationForm {
+    /// Canonical composition, "e\u{301}" and "é" compare equal.
+    Nfc,
+    /// Compatibility composition, additionally "ﬁ" and "fi" or "①" and "1" compare equal.
+    Nfkc,
+}
+
+/// Steps applied to both the secret word and the attempt before they are compared.
+#[derive(Debug, Clone)]
+pub struct AnswerNormalization {
+    pub form: NormalizationForm,
+    /// Full Unicode case folding, "Straße" and "STRASSE" compare equal.
+    pub case_folding: bool,
+    /// Drops accents and other combining marks, "café" and "cafe" compare equal.
+    pub strip_diacritics: bool,
+    /// Ignores whitespace around the answer.
+    pub trim_whitespace: bool,
+    /// Ignores punctuation and symbols around the answer, "test!" and "test" compare equal.
+    pub trim_punctuation: bool,
+}
+
+impl Default for AnswerNormalization {
+    fn default() -> Self {
+        AnswerNormalization {
+            form: NormalizationForm::Nfc,
+            case_folding: true,
+            strip_diacritics: false,
+            trim_whitespace: true,
+            trim_punctuation: false,
+        }
+    }
+}
+
+impl AnswerNormalization {
+    pub fn apply(&self, text: &str) -> String {
+        let mut text = if self.trim_whitespace { text.trim() } else { text }.to_string();
+
+        if self.trim_punctuation {
+            text = text
+                .trim_matches(|c: char| !c.is_alphanumeric() && !c.is_whitespace())
+                .to_string();
+
+            if self.trim_whitespace {
+                text = text.trim().to_string();
+            }
+        }
+
+        if self.case_folding {
+            text = default_case_fold_str(&text);
+        }
+
+        if self.strip_diacritics {
+            text = text.nfd().filter(|c| !is_combining_mark(*c)).collect();
+        }
+
+        match self.form {
+            NormalizationForm::Nfc => text.nfc().collect(),
+            NormalizationForm::Nfkc => text.nfkc().collect(),
+        }
+    }
+}
@@ -0,0 +1,515 @@
+use std::time::Duration;
+use anyhow::bail;
+use crate::bin_reader::BinReader;
+use crate::bin_writer::BinWriter;
+use crate::server::error::{ErrorCode, GameError};
+use crate::server::game::MatchMode;
+use crate::server::solo::SERVER_ID;
+use crate::utils::{unix_millis, ClientId};
+
+/// Most pairings a tournament can have. The whole bracket is pushed in a single frame, 32 pairings
+/// stay below `MAX_PACKET_LENGTH` and below `MAX_JSON_LINE_LENGTH` for JSON clients.
+pub const MAX_TOURNAMENT_PAIRINGS: usize = 32;
+
+#[derive(Debug, Clone)]
+pub struct TournamentRules {
+    /// How long a challenger has to send the word of a `WordSource::Players` match, the guesser
+    /// wins the pairing once it's over.
+    pub word_timeout: Duration,
+}
+
+impl Default for TournamentRules {
+    fn default() -> Self {
+        TournamentRules { word_timeout: Duration::from_secs(60) }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(u8)]
+pub enum TournamentFormat {
+    /// Winners of a round meet in the next one until a single player is left.
+    SingleElimination,
+    /// Every player meets every other player once, most points win.
+    RoundRobin
+}
+
+impl TryFrom<u8> for TournamentFormat {
+    type Error = anyhow::Error;
+
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        match v {
+            x if x == TournamentFormat::SingleElimination as u8 => Ok(TournamentFormat::SingleElimination),
+            x if x == TournamentFormat::RoundRobin as u8 => Ok(TournamentFormat::RoundRobin),
+            _ => bail!("Couldn't convert {} to TournamentFormat", v),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(u8)]
+pub enum WordSource {
+    /// Both players of a pairing guess the same word picked by the server at the same time.
+    Server,
+    /// Players of a pairing take turns setting the word for each other.
+    Players
+}
+
+impl TryFrom<u8> for WordSource {
+    type Error = anyhow::Error;
+
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        match v {
+            x if x == WordSource::Server as u8 => Ok(WordSource::Server),
+            x if x == WordSource::Players as u8 => Ok(WordSource::Players),
+            _ => bail!("Couldn't convert {} to WordSource", v),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(u8)]
+pub enum PairingState {
+    Pending,
+    Playing,
+    Finished
+}
+
+impl TryFrom<u8> for PairingState {
+    type Error = anyhow::Error;
+
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        match v {
+            x if x == PairingState::Pending as u8 => Ok(PairingState::Pending),
+            x if x == PairingState::Playing as u8 => Ok(PairingState::Playing),
+            x if x == PairingState::Finished as u8 => Ok(PairingState::Finished),
+            _ => bail!("Couldn't convert {} to PairingState", v),
+        }
+    }
+}
+
+/// Two players meeting in a tournament. Each of them guesses once, the higher guesser score wins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TournamentPairing {
+    pub round: u16,
+    pub first: ClientId,
+    pub second: ClientId,
+    pub first_score: Option<u32>,
+    pub second_score: Option<u32>,
+    pub state: PairingState,
+    /// `None` for a draw or while the pairing isn't finished.
+    pub winner: Option<ClientId>,
+}
+
+impl TournamentPairing {
+    fn new(round: u16, first: ClientId, second: ClientId) -> TournamentPairing {
+        TournamentPairing {
+            round,
+            first,
+            second,
+            first_score: None,
+            second_score: None,
+            state: PairingState::Pending,
+            winner: None,
+        }
+    }
+
+    fn includes(&self, id: ClientId) -> bool {
+        self.first == id || self.second == id
+    }
+
+    fn other(&self, id: ClientId) -> ClientId {
+        if self.first == id { self.second } else { self.first }
+    }
+
+    pub fn write(&self, bin_writer: &mut BinWriter) {
+        bin_writer.write_u16(self.round);
+        bin_writer.write_u32(self.first);
+        bin_writer.write_u32(self.second);
+        for score in [self.first_score, self.second_score] {
+            bin_writer.write_u8(score.is_some() as u8);
+            bin_writer.write_u32(score.unwrap_or_default());
+        }
+        bin_writer.write_u8(self.state as u8);
+        bin_writer.write_u32(self.winner.unwrap_or(SERVER_ID));
+    }
+
+    pub fn read(bin_reader: &mut BinReader) -> anyhow::Result<TournamentPairing> {
+        let round = bin_reader.read_u16()?;
+        let first = bin_reader.read_u32()?;
+        let second = bin_reader.read_u32()?;
+        let mut read_score = || {
+            let has_score = bin_reader.read_u8()? > 0;
+            let score = bin_reader.read_u32()?;
+            anyhow::Ok(has_score.then_some(score))
+        };
+        let first_score = read_score()?;
+        let second_score = read_score()?;
+
+        Ok(TournamentPairing {
+            round,
+            first,
+            second,
+            first_score,
+            second_score,
+            state: PairingState::try_from(bin_reader.read_u8()?)?,
+            winner: Some(bin_reader.read_u32()?).filter(|id| *id != SERVER_ID),
+        })
+    }
+}
+
+/// A match of a pairing, the challenger is `SERVER_ID` when the server picks the word.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Leg {
+    pub pairing: usize,
+    pub challenger_id: ClientId,
+    pub guesser_id: ClientId,
+    /// Whether the match began, legs with player words wait for the challenger's word first.
+    pub started: bool,
+    /// When the leg was scheduled, in unix milliseconds.
+    pub scheduled_at: u64,
+}
+
+/// Pairings, results and the matches being played of a tournament within a room.
+pub struct Tournament {
+    pub organizer_id: ClientId,
+    pub format: TournamentFormat,
+    pub words: WordSource,
+    pub mode: MatchMode,
+    players: Vec<ClientId>,
+    pairings: Vec<TournamentPairing>,
+    legs: Vec<Leg>,
+    // players that left, they lose every pairing that isn't finished yet
+    forfeited: Vec<ClientId>,
+    // player without an opponent in the current elimination round
+    bye: Option<ClientId>,
+    winner: Option<ClientId>,
+    // whether the bracket changed since it was last sent
+    changed: bool,
+}
+
+impl Tournament {
+    pub fn new(
+        organizer_id: ClientId,
+        format: TournamentFormat,
+        words: WordSource,
+        mode: MatchMode,
+        players: &[ClientId]) -> anyhow::Result<Tournament> {
+
+        if players.len() < 2 {
+            bail!("A tournament needs at least two players");
+        }
+
+        for (i, id) in players.iter().enumerate() {
+            if players[..i].contains(id) {
+                bail!("Player {} can't join the tournament twice.", id);
+            }
+        }
+
+        let pairings = match format {
+            TournamentFormat::SingleElimination => players.len() - 1,
+            TournamentFormat::RoundRobin => players.len() * (players.len() - 1) / 2,
+        };
+        if pairings > MAX_TOURNAMENT_PAIRINGS {
+            bail!(GameError::new(
+                ErrorCode::TournamentTooLarge,
+                format!("A tournament can't have more than {} pairings", MAX_TOURNAMENT_PAIRINGS)));
+        }
+
+        let mut tournament = Tournament {
+            organizer_id,
+            format,
+            words,
+            mode,
+            players: players.to_vec(),
+            pairings: Vec::new(),
+            legs: Vec::new(),
+            forfeited: Vec::new(),
+            bye: None,
+            winner: None,
+            changed: true,
+        };
+
+        match format {
+            TournamentFormat::SingleElimination => tournament.pair_round(1, players.to_vec()),
+            TournamentFormat::RoundRobin => {
+                for (i, first) in players.iter().enumerate() {
+                    for second in &players[i + 1..] {
+                        tournament.pairings.push(TournamentPairing::new(1, *first, *second));
+                    }
+                }
+            }
+        }
+
+        Ok(tournament)
+    }
+
+    /// Players and the organizer, everyone who is sent bracket updates.
+    pub fn participants(&self) -> Vec<ClientId> {
+        let mut participants = self.players.clone();
+        if !participants.contains(&self.organizer_id) {
+            participants.push(self.organizer_id);
+        }
+        participants
+    }
+
+    pub fn pairings(&self) -> &[TournamentPairing] {
+        &self.pairings
+    }
+
+    /// Set once the tournament is over, `SERVER_ID` when every player left.
+    pub fn winner(&self) -> Option<ClientId> {
+        self.winner
+    }
+
+    /// Whether the player still has pairings ahead, the bracket would wait for it forever if it
+    /// left the room.
+    pub fn is_competing(&self, id: ClientId) -> bool {
+        if self.winner.is_some() || !self.players.contains(&id) || self.forfeited.contains(&id) {
+            return false;
+        }
+
+        match self.format {
+            TournamentFormat::SingleElimination => !self.pairings
+                .iter()
+                .any(|p| p.state == PairingState::Finished && p.includes(id) && p.winner != Some(id)),
+            TournamentFormat::RoundRobin => self.pairings
+                .iter()
+                .any(|p| p.state != PairingState::Finished && p.includes(id)),
+        }
+    }
+
+    /// Whether the bracket changed since the last call.
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::replace(&mut self.changed, false)
+    }
+
+    /// Starts the pairings and legs whose players are free, returns the legs that have to begin.
+    pub fn schedule(&mut self, available: impl Fn(ClientId) -> bool) -> Vec<Leg> {
+        let mut legs = Vec::new();
+
+        for index in 0..self.pairings.len() {
+            let pairing = &self.pairings[index];
+            if !available(pairing.first) || !available(pairing.second) {
+                continue;
+            }
+
+            match pairing.state {
+                PairingState::Pending => {
+                    // players are only in one pairing at a time
+                    let busy = self.pairings
+                        .iter()
+                        .any(|p| p.state == PairingState::Playing && (p.includes(pairing.first) || p.includes(pairing.second)));
+                    if busy {
+                        continue;
+                    }
+
+                    self.pairings[index].state = PairingState::Playing;
+                    self.changed = true;
+
+                    let (first, second) = (self.pairings[index].first, self.pairings[index].second);
+                    match self.words {
+                        WordSource::Server => {
+                            legs.push(self.add_leg(index, SERVER_ID, first));
+                            legs.push(self.add_leg(index, SERVER_ID, second));
+                        }
+                        WordSource::Players => legs.push(self.add_leg(index, first, second)),
+                    }
+                }
+                // players take turns, the first player guesses once the second player's guess is over
+                PairingState::Playing if self.words == WordSource::Players => {
+                    let has_leg = self.legs.iter().any(|l| l.pairing == index);
+                    if !has_leg && pairing.second_score.is_some() && pairing.first_score.is_none() {
+                        let (first, second) = (pairing.first, pairing.second);
+                        legs.push(self.add_leg(index, second, first));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        legs
+    }
+
+    /// The leg waiting for the challenger's word.
+    pub fn awaiting_word(&self, challenger_id: ClientId) -> Option<&Leg> {
+        self.legs.iter().find(|l| l.challenger_id == challenger_id && !l.started)
+    }
+
+    pub fn start_leg(&mut self, challenger_id: ClientId, guesser_id: ClientId) {
+        if let Some(leg) = self.legs.iter_mut().find(|l| l.challenger_id == challenger_id && l.guesser_id == guesser_id) {
+            leg.started = true;
+        }
+    }
+
+    /// Gives the pairings whose challengers didn't send a word within `timeout` to the guessers,
+    /// returns the legs that were dropped.
+    pub fn expire_words(&mut self, timeout: Duration) -> Vec<Leg> {
+        let deadline = unix_millis().saturating_sub(timeout.as_millis() as u64);
+        let expired: Vec<Leg> = self.legs
+            .iter()
+            .filter(|l| !l.started && l.challenger_id != SERVER_ID && l.scheduled_at <= deadline)
+            .copied()
+            .collect();
+
+        for leg in &expired {
+            self.legs.retain(|l| l.pairing != leg.pairing);
+            self.finish_pairing(leg.pairing, Some(leg.guesser_id));
+        }
+
+        expired
+    }
+
+    /// Records the guesser's score if the match was a leg of this tournament.
+    pub fn record_leg(&mut self, challenger_id: ClientId, guesser_id: ClientId, score: u32) {
+        let Some(position) = self.legs
+            .iter()
+            .position(|l| l.started && l.challenger_id == challenger_id && l.guesser_id == guesser_id) else {
+            return;
+        };
+
+        let leg = self.legs.remove(position);
+        let pairing = &mut self.pairings[leg.pairing];
+        if pairing.first == guesser_id {
+            pairing.first_score = Some(score);
+        } else {
+            pairing.second_score = Some(score);
+        }
+        self.changed = true;
+
+        if pairing.first_score.is_some() && pairing.second_score.is_some() {
+            let winner = match pairing.first_score.cmp(&pairing.second_score) {
+                std::cmp::Ordering::Greater => Some(pairing.first),
+                std::cmp::Ordering::Less => Some(pairing.second),
+                // ties go to the higher seed when someone has to advance
+                std::cmp::Ordering::Equal => match self.format {
+                    TournamentFormat::SingleElimination => Some(pairing.first),
+                    TournamentFormat::RoundRobin => None,
+                }
+            };
+            self.finish_pairing(leg.pairing, winner);
+        }
+    }
+
+    /// Gives every unfinished pairing of a player that left to the opponent. Legs the player was
+    /// still playing have to be ended before. Returns the opponents that were waiting for a word.
+    pub fn forfeit(&mut self, id: ClientId) -> Vec<ClientId> {
+        if !self.players.contains(&id) || self.forfeited.contains(&id) {
+            return Vec::new();
+        }
+
+        self.forfeited.push(id);
+        self.changed = true;
+
+        let waiting = self.legs
+            .iter()
+            .filter(|l| !l.started && (l.challenger_id == id || l.guesser_id == id))
+            .map(|l| if l.challenger_id == id { l.guesser_id } else { l.challenger_id })
+            .collect();
+
+        let open: Vec<usize> = (0..self.pairings.len())
+            .filter(|i| self.pairings[*i].state != PairingState::Finished && self.pairings[*i].includes(id))
+            .collect();
+
+        for index in open {
+            self.legs.retain(|l| l.pairing != index);
+            let other = self.pairings[index].other(id);
+            let winner = (!self.forfeited.contains(&other)).then_some(other);
+            self.finish_pairing(index, winner);
+        }
+
+        if self.bye == Some(id) {
+            self.bye = None;
+            self.advance_round();
+        }
+
+        waiting
+    }
+
+    fn add_leg(&mut self, pairing: usize, challenger_id: ClientId, guesser_id: ClientId) -> Leg {
+        let leg = Leg {
+            pairing,
+            challenger_id,
+            guesser_id,
+            started: false,
+            scheduled_at: unix_millis(),
+        };
+        self.legs.push(leg);
+        leg
+    }
+
+    fn finish_pairing(&mut self, index: usize, winner: Option<ClientId>) {
+        let pairing = &mut self.pairings[index];
+        pairing.state = PairingState::Finished;
+        pairing.winner = winner;
+        self.advance_round();
+    }
+
+    /// Pairs the next elimination round or picks the winner once every pairing is finished.
+    fn advance_round(&mut self) {
+        if self.winner.is_some() || self.pairings.iter().any(|p| p.state != PairingState::Finished) {
+            return;
+        }
+
+        match self.format {
+            TournamentFormat::SingleElimination => {
+                let round = self.pairings.last().map(|p| p.round).unwrap_or_default();
+                let mut advancing: Vec<ClientId> = self.pairings
+                    .iter()
+                    .filter(|p| p.round == round)
+                    .filter_map(|p| p.winner)
+                    .collect();
+                advancing.extend(self.bye.take());
+                advancing.retain(|id| !self.forfeited.contains(id));
+
+                if advancing.len() <= 1 {
+                    self.set_winner(advancing.first().copied());
+                } else {
+                    self.pair_round(round + 1, advancing);
+                }
+            }
+            TournamentFormat::RoundRobin => {
+                let points = |id: ClientId| -> (u32, u32) {
+                    self.pairings.iter().filter(|p| p.includes(id)).fold((0, 0), |(points, score), p| {
+                        let won = match p.winner {
+                            Some(winner) if winner == id => 2,
+                            None if !self.forfeited.contains(&id) => 1,
+                            _ => 0,
+                        };
+                        let own = if p.first == id { p.first_score } else { p.second_score };
+                        (points + won, score + own.unwrap_or_default())
+                    })
+                };
+
+                // the earlier player wins a tie
+                let winner = self.players
+                    .iter()
+                    .filter(|id| !self.forfeited.contains(id))
+                    .rev()
+                    .max_by_key(|id| points(**id))
+                    .copied();
+                self.set_winner(winner);
+            }
+        }
+    }
+
+    fn pair_round(&mut self, round: u16, players: Vec<ClientId>) {
+        for pair in players.chunks(2) {
+            match pair {
+                [first, second] => self.pairings.push(TournamentPairing::new(round, *first, *second)),
+                [single] => self.bye = Some(*single),
+                _ => {}
+            }
+        }
+        self.changed = true;
+    }
+
+    fn set_winner(&mut self, winner: Option<ClientId>) {
+        // a tournament where everyone left ends without a winner
+        self.winner = winner.or(Some(SERVER_ID));
+        self.changed = true;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.winner.is_some()
+    }
+}
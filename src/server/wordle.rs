@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use anyhow::bail;
+use crate::bin_reader::BinReader;
+use crate::bin_writer::BinWriter;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(u8)]
+pub enum LetterFeedback {
+    /// The letter is in the word at this position.
+    Correct,
+    /// The letter is in the word at another position.
+    Present,
+    /// The letter isn't in the word, or all its occurrences are already accounted for.
+    Absent
+}
+
+impl TryFrom<u8> for LetterFeedback {
+    type Error = anyhow::Error;
+
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        match v {
+            x if x == LetterFeedback::Correct as u8 => Ok(LetterFeedback::Correct),
+            x if x == LetterFeedback::Present as u8 => Ok(LetterFeedback::Present),
+            x if x == LetterFeedback::Absent as u8 => Ok(LetterFeedback::Absent),
+            _ => bail!("Couldn't convert {} to LetterFeedback", v),
+        }
+    }
+}
+
+/// Result of an attempt, `letters` is only filled in `MatchMode::Wordle` matches.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttemptFeedback {
+    pub correct: bool,
+    pub letters: Vec<LetterFeedback>,
+}
+
+impl AttemptFeedback {
+    pub fn write(&self, bin_writer: &mut BinWriter) {
+        bin_writer.write_u8(self.correct as u8);
+        write_letters(&self.letters, bin_writer);
+    }
+
+    pub fn read(bin_reader: &mut BinReader) -> anyhow::Result<AttemptFeedback> {
+        Ok(AttemptFeedback {
+            correct: bin_reader.read_u8()? > 0,
+            letters: read_letters(bin_reader)?,
+        })
+    }
+}
+
+pub fn write_letters(letters: &[LetterFeedback], bin_writer: &mut BinWriter) {
+    bin_writer.write_u16(letters.len() as u16);
+    for letter in letters {
+        bin_writer.write_u8(*letter as u8);
+    }
+}
+
+pub fn read_letters(bin_reader: &mut BinReader) -> anyhow::Result<Vec<LetterFeedback>> {
+    let count = bin_reader.read_u16()? as usize;
+    let mut letters = Vec::with_capacity(count);
+    for _ in 0..count {
+        letters.push(LetterFeedback::try_from(bin_reader.read_u8()?)?);
+    }
+
+    Ok(letters)
+}
+
+/// Compares the attempt letter by letter, both words are expected to be normalized already.
+pub fn letter_feedback(secret: &str, attempt: &str) -> Vec<LetterFeedback> {
+    let secret: Vec<char> = secret.chars().collect();
+    let attempt: Vec<char> = attempt.chars().collect();
+    let mut feedback = vec![LetterFeedback::Absent; attempt.len()];
+
+    // letters of the secret that weren't guessed at their position
+    let mut remaining: HashMap<char, usize> = HashMap::new();
+    for (i, c) in secret.iter().enumerate() {
+        if attempt.get(i) == Some(c) {
+            feedback[i] = LetterFeedback::Correct;
+        } else {
+            *remaining.entry(*c).or_default() += 1;
+        }
+    }
+
+    for (i, c) in attempt.iter().enumerate() {
+        if feedback[i] == LetterFeedback::Correct {
+            continue;
+        }
+
+        if let Some(count) = remaining.get_mut(c).filter(|count| **count > 0) {
+            *count -= 1;
+            feedback[i] = LetterFeedback::Present;
+        }
+    }
+
+    feedback
+}
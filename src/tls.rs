@@ -0,0 +1,147 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use anyhow::{bail, Context};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{ring, verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, RootCertStore, SignatureScheme};
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+/// Certificate chain and private key the server presents, both PEM files.
+#[derive(Debug, Clone)]
+pub struct ServerTlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+/// How the client decides to trust the server's certificate.
+#[derive(Debug, Clone)]
+pub enum ServerTrust {
+    /// Certificates issued by the CA in this PEM file.
+    Ca(PathBuf),
+    /// Only the exact certificate in this PEM file, the name and expiry aren't checked.
+    Pinned(PathBuf),
+}
+
+#[derive(Debug, Clone)]
+pub struct ClientTlsConfig {
+    /// Name the server's certificate has to be issued for.
+    pub server_name: String,
+    pub trust: ServerTrust,
+}
+
+impl ServerTlsConfig {
+    pub fn acceptor(&self) -> anyhow::Result<TlsAcceptor> {
+        let certs = load_certs(&self.cert_path)?;
+        let key = load_key(&self.key_path)?;
+
+        let config = rustls::ServerConfig::builder_with_provider(provider())
+            .with_safe_default_protocol_versions()?
+            .with_no_client_auth()
+            .with_single_cert(certs, key)?;
+
+        Ok(TlsAcceptor::from(Arc::new(config)))
+    }
+}
+
+impl ClientTlsConfig {
+    pub fn connector(&self) -> anyhow::Result<TlsConnector> {
+        let builder = rustls::ClientConfig::builder_with_provider(provider())
+            .with_safe_default_protocol_versions()?;
+
+        let config = match &self.trust {
+            ServerTrust::Ca(path) => {
+                let mut roots = RootCertStore::empty();
+                for cert in load_certs(path)? {
+                    roots.add(cert)?;
+                }
+                builder.with_root_certificates(roots).with_no_client_auth()
+            }
+            ServerTrust::Pinned(path) => {
+                let Some(cert) = load_certs(path)?.into_iter().next() else {
+                    bail!("No certificate found in {}", path.display());
+                };
+                builder
+                    .dangerous()
+                    .with_custom_certificate_verifier(Arc::new(PinnedCertVerifier { cert, provider: provider() }))
+                    .with_no_client_auth()
+            }
+        };
+
+        Ok(TlsConnector::from(Arc::new(config)))
+    }
+
+    pub fn server_name(&self) -> anyhow::Result<ServerName<'static>> {
+        Ok(ServerName::try_from(self.server_name.clone())?)
+    }
+}
+
+fn provider() -> Arc<CryptoProvider> {
+    Arc::new(ring::default_provider())
+}
+
+fn load_certs(path: &Path) -> anyhow::Result<Vec<CertificateDer<'static>>> {
+    let file = File::open(path).with_context(|| format!("Couldn't open {}", path.display()))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file)).collect::<Result<Vec<_>, _>>()?;
+
+    if certs.is_empty() {
+        bail!("No certificate found in {}", path.display());
+    }
+    Ok(certs)
+}
+
+fn load_key(path: &Path) -> anyhow::Result<PrivateKeyDer<'static>> {
+    let file = File::open(path).with_context(|| format!("Couldn't open {}", path.display()))?;
+    match rustls_pemfile::private_key(&mut BufReader::new(file))? {
+        Some(key) => Ok(key),
+        None => bail!("No private key found in {}", path.display()),
+    }
+}
+
+/// Accepts only a single known certificate, signatures of the handshake are still checked.
+#[derive(Debug)]
+struct PinnedCertVerifier {
+    cert: CertificateDer<'static>,
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if end_entity.as_ref() == self.cert.as_ref() {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General("Server certificate doesn't match the pinned certificate".to_string()))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider.signature_verification_algorithms.supported_schemes()
+    }
+}
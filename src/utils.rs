@@ -1,14 +1,30 @@
-use tokio::net::TcpStream;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio_util::codec::{Framed, LengthDelimitedCodec};
 
 pub const MAX_PACKET_LENGTH: usize = 1024;
 
 pub type ClientId = u32;
 
-pub fn create_framed_stream(stream: TcpStream) -> Framed<TcpStream, LengthDelimitedCodec> {
+/// Any stream the protocol can run over: TCP, TLS, in-memory pipes or custom tunnels.
+pub trait AsyncStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> AsyncStream for T {}
+
+pub type BoxedStream = Box<dyn AsyncStream>;
+
+pub fn create_framed_stream<S: AsyncRead + AsyncWrite>(stream: S) -> Framed<S, LengthDelimitedCodec> {
    LengthDelimitedCodec::builder()
         .length_field_type::<u16>()
         .little_endian()
         .max_frame_length(MAX_PACKET_LENGTH)
         .new_framed(stream)
-}
\ No newline at end of file
+}
+
+/// Milliseconds since the unix epoch, used for timestamps stored on disk and sent over the wire.
+pub fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,11 @@
+; ModuleID = 'autocfg_f15b6f8a6a102bc9_0.97b98af43ddf2765-cgu.0'
+source_filename = "autocfg_f15b6f8a6a102bc9_0.97b98af43ddf2765-cgu.0"
+target datalayout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-i128:128-f80:128-n8:16:32:64-S128"
+target triple = "x86_64-unknown-linux-gnu"
+
+!llvm.module.flags = !{!0, !1}
+!llvm.ident = !{!2}
+
+!0 = !{i32 8, !"PIC Level", i32 2}
+!1 = !{i32 2, !"RtLibUseGOT", i32 1}
+!2 = !{!"rustc version 1.95.0 (59807616e 2026-04-14)"}